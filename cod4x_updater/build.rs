@@ -0,0 +1,15 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=COD4X_RELEASE_PUBLIC_KEY");
+    println!("cargo:rerun-if-env-changed=COD4X_REQUIRE_RELEASE_KEY");
+
+    // Unset CI variables are passed as empty strings
+    let is_set = |name: &str| std::env::var(name).is_ok_and(|value| !value.trim().is_empty());
+    if !is_set("COD4X_RELEASE_PUBLIC_KEY") {
+        // Published builds without the key would reject every signed release
+        if is_set("COD4X_REQUIRE_RELEASE_KEY") {
+            panic!("COD4X_RELEASE_PUBLIC_KEY must be set when COD4X_REQUIRE_RELEASE_KEY is");
+        }
+        println!("cargo:warning=COD4X_RELEASE_PUBLIC_KEY is not set, the updater will reject all signed releases");
+    }
+}
@@ -0,0 +1,16 @@
+use std::io::BufReader;
+
+/// Applies a binary delta in the format of the `bsdiff` crate to `base` and writes the result to
+/// `output`.
+pub fn apply(
+    base: &std::path::Path,
+    patch: &std::path::Path,
+    output: &std::path::Path,
+) -> std::io::Result<()> {
+    let base = std::fs::read(base)?;
+    let mut patch = BufReader::new(std::fs::File::open(patch)?);
+
+    let mut patched = Vec::new();
+    bsdiff::patch(&base, &mut patch, &mut patched)?;
+    std::fs::write(output, patched)
+}
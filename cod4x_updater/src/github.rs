@@ -0,0 +1,315 @@
+use crate::http;
+use crate::release::{self, AssetInformation, Channel, ParseError, ReleaseInformation};
+use crate::source::ReleaseSource;
+use crate::time::unix_time;
+use semver::Version;
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+const RATE_LIMIT_FILE_NAME: &str = "github-rate-limit";
+
+/// Unix time until which the API is known to be rate-limited for this process.
+static RATE_LIMIT_RESET: AtomicU64 = AtomicU64::new(0);
+
+pub struct GitHubSource {
+    repository_path: String,
+    cache_dir: Option<PathBuf>,
+    token: Option<String>,
+}
+
+impl GitHubSource {
+    /// API responses are cached in `cache_dir` and revalidated with their ETag. Unchanged
+    /// responses to authenticated requests don't count against the rate limit, unauthenticated
+    /// ones still do. A `token` raises the rate limit from 60 to 5000 requests per hour.
+    pub fn new(repository_path: &str, cache_dir: Option<&Path>, token: Option<&str>) -> Self {
+        Self {
+            repository_path: repository_path.to_string(),
+            cache_dir: cache_dir.map(|cache_dir| cache_dir.to_path_buf()),
+            token: token.map(|token| token.to_string()),
+        }
+    }
+
+    fn fetch_release_json(
+        &self,
+        channel: Channel,
+        cached: Option<&CachedResponse>,
+    ) -> anyhow::Result<Option<http::Response>> {
+        // The latest release is never a prerelease, other channels need to look through the list
+        let endpoint = match channel {
+            Channel::Stable => "releases/latest",
+            _ => "releases?per_page=30",
+        };
+        let url = format!(
+            "https://api.github.com/repos/{}/{endpoint}",
+            self.repository_path
+        );
+
+        let rate_limit_reset = self.rate_limit_reset();
+        if rate_limit_reset > unix_time() {
+            return Err(RateLimitError::new(rate_limit_reset).into());
+        }
+
+        let mut headers = Vec::new();
+        if let Some(token) = self.token.as_ref() {
+            headers.push(format!("Authorization: Bearer {token}"));
+        }
+        if let Some(etag) = cached.and_then(|cached| cached.etag.as_ref()) {
+            headers.push(format!("If-None-Match: {etag}"));
+        }
+
+        let response = http::get(url.as_str(), &headers, Some(http::INFO_TIMEOUT))?;
+        match response.status {
+            200 => Ok(Some(response)),
+            304 => Ok(None),
+            403 | 429 => match get_rate_limit_reset(&response) {
+                Some(reset) => {
+                    self.set_rate_limit_reset(reset);
+                    Err(RateLimitError::new(reset).into())
+                }
+                None => Err(ParseError::FetchError.into()),
+            },
+            _ => Err(ParseError::FetchError.into()),
+        }
+    }
+
+    fn cache_path(&self, channel: Channel) -> Option<PathBuf> {
+        let cache_name = match channel {
+            Channel::Stable => "latest",
+            _ => "releases",
+        };
+        let repository_name = self.repository_path.replace('/', "-");
+
+        self.cache_dir
+            .as_ref()
+            .map(|cache_dir| cache_dir.join(format!("github-{repository_name}-{cache_name}.json")))
+    }
+
+    /// The rate limit is per client address, so it's shared by all repositories and persisted
+    /// to avoid hitting the API again on the next start.
+    fn rate_limit_reset(&self) -> u64 {
+        let persisted_reset = self
+            .cache_dir
+            .as_ref()
+            .and_then(|cache_dir| {
+                std::fs::read_to_string(cache_dir.join(RATE_LIMIT_FILE_NAME)).ok()
+            })
+            .and_then(|reset| reset.trim().parse().ok())
+            .unwrap_or(0);
+
+        RATE_LIMIT_RESET
+            .load(Ordering::Relaxed)
+            .max(persisted_reset)
+    }
+
+    fn set_rate_limit_reset(&self, reset: u64) {
+        RATE_LIMIT_RESET.fetch_max(reset, Ordering::Relaxed);
+        if let Some(cache_dir) = self.cache_dir.as_ref() {
+            _ = std::fs::create_dir_all(cache_dir).and_then(|_| {
+                std::fs::write(cache_dir.join(RATE_LIMIT_FILE_NAME), reset.to_string())
+            });
+        }
+    }
+}
+
+impl ReleaseSource for GitHubSource {
+    fn name(&self) -> String {
+        format!("GitHub ({})", self.repository_path)
+    }
+
+    fn fetch_release_information(&self, channel: Channel) -> anyhow::Result<ReleaseInformation> {
+        Ok(release::parse_releases(
+            &self.fetch_releases_json(channel)?,
+            None,
+            channel,
+        )?)
+    }
+
+    /// Only the 30 most recent releases are looked through.
+    fn fetch_release_by_version(&self, version: &Version) -> anyhow::Result<ReleaseInformation> {
+        Ok(release::find_release(
+            &self.fetch_releases_json(Channel::Nightly)?,
+            None,
+            version,
+        )?)
+    }
+}
+
+impl GitHubSource {
+    /// The latest release on the stable channel, a list of releases otherwise.
+    fn fetch_releases_json(&self, channel: Channel) -> anyhow::Result<json::Value> {
+        let cache_path = self.cache_path(channel);
+        let cached = cache_path.as_deref().and_then(CachedResponse::load);
+
+        let response_json = match self.fetch_release_json(channel, cached.as_ref()) {
+            Ok(Some(response)) => {
+                let response_json: json::Value = json::from_slice(response.body.as_slice())
+                    .map_err(|_| ParseError::InvalidResponse)?;
+                if let Some(cache_path) = cache_path.as_deref() {
+                    let cached = CachedResponse {
+                        etag: response.header("ETag").map(|etag| etag.to_string()),
+                        response: response_json.clone(),
+                    };
+                    // Not being able to cache the response only costs a request next time
+                    _ = cached.store(cache_path);
+                }
+                response_json
+            }
+            // Not modified, or GitHub can't be reached or is rate-limited: reuse the last response
+            Ok(None) => cached.ok_or(ParseError::FetchError)?.response,
+            Err(e) => match cached {
+                Some(cached) => cached.response,
+                None => return Err(e),
+            },
+        };
+
+        Ok(response_json)
+    }
+}
+
+/// Resolves the latest release through the github.com download pages instead of the API, which
+/// keeps working when the API is rate-limited or changes. Assets are taken from `hashes.txt`.
+/// Only the latest stable release is visible this way, whatever the channel or version.
+pub struct GitHubDownloadSource {
+    repository_path: String,
+}
+
+impl GitHubDownloadSource {
+    pub fn new(repository_path: &str) -> Self {
+        Self {
+            repository_path: repository_path.to_string(),
+        }
+    }
+}
+
+impl ReleaseSource for GitHubDownloadSource {
+    fn name(&self) -> String {
+        format!("GitHub downloads ({})", self.repository_path)
+    }
+
+    fn fetch_release_information(&self, _channel: Channel) -> anyhow::Result<ReleaseInformation> {
+        let repository_path = self.repository_path.as_str();
+
+        // github.com/<repository>/releases/latest redirects to .../releases/tag/<tag>
+        let latest_url = format!("https://github.com/{repository_path}/releases/latest");
+        let release_url = http::resolve_url(latest_url.as_str(), Some(http::INFO_TIMEOUT))
+            .map_err(|_| ParseError::FetchError)?;
+        let (_, tag_name) = release_url
+            .split_once("/releases/tag/")
+            .ok_or(ParseError::TagName)?;
+
+        let download_url =
+            format!("https://github.com/{repository_path}/releases/download/{tag_name}");
+        let hashes_url = format!("{download_url}/hashes.txt");
+        let hashes = http::download_str(hashes_url.as_str(), Some(http::INFO_TIMEOUT))
+            .map_err(|_| ParseError::ReleaseAssets)?;
+
+        // The signature is listed even if the release doesn't publish one, fetching it then
+        // fails with 404 and the signature policy decides
+        let assets = ["hashes.txt", "hashes.txt.sig"]
+            .into_iter()
+            .chain(release::parse_hashes(hashes.as_str()).into_keys())
+            .map(|name| AssetInformation {
+                name: name.to_string(),
+                url: format!("{download_url}/{name}"),
+                size: None,
+            })
+            .collect();
+
+        Ok(ReleaseInformation {
+            tag_name: tag_name.to_string(),
+            name: None,
+            published_at: None,
+            body: None,
+            security: false,
+            assets,
+            source: String::new(),
+        })
+    }
+
+    fn fetch_release_by_version(&self, version: &Version) -> anyhow::Result<ReleaseInformation> {
+        let release_information = self.fetch_release_information(Channel::Stable)?;
+        match release::parse_version(release_information.tag_name.as_str()) {
+            Ok(release_version) if release_version == *version => Ok(release_information),
+            _ => Err(ParseError::NoRelease.into()),
+        }
+    }
+}
+
+struct CachedResponse {
+    etag: Option<String>,
+    response: json::Value,
+}
+
+impl CachedResponse {
+    fn load(path: &Path) -> Option<Self> {
+        let cached_str = std::fs::read_to_string(path).ok()?;
+        let cached_json: json::Value = json::from_str(cached_str.as_str()).ok()?;
+
+        Some(Self {
+            etag: cached_json
+                .pointer("/etag")
+                .and_then(|etag| etag.as_str())
+                .map(|etag| etag.to_string()),
+            response: cached_json.pointer("/response")?.clone(),
+        })
+    }
+
+    fn store(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let cached_json = json::json!({
+            "etag": self.etag,
+            "response": self.response,
+        });
+        std::fs::write(path, cached_json.to_string())?;
+        Ok(())
+    }
+}
+
+/// Reset time of an exhausted primary rate limit, or of a secondary rate limit from `Retry-After`.
+fn get_rate_limit_reset(response: &http::Response) -> Option<u64> {
+    if let Some(retry_after) = response.header("Retry-After") {
+        return retry_after
+            .parse::<u64>()
+            .ok()
+            .map(|retry_after| unix_time() + retry_after);
+    }
+
+    if response.header("X-RateLimit-Remaining") != Some("0") {
+        return None;
+    }
+    response.header("X-RateLimit-Reset")?.parse().ok()
+}
+
+struct RateLimitError {
+    message: String,
+}
+
+impl RateLimitError {
+    fn new(reset: u64) -> Self {
+        let minutes = reset.saturating_sub(unix_time()).div_ceil(60).max(1);
+        Self {
+            message: format!(
+                "GitHub API rate limit exceeded, try again in {minutes} minute(s) or configure a GitHub token"
+            ),
+        }
+    }
+}
+
+impl Display for RateLimitError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Debug for RateLimitError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for RateLimitError {}
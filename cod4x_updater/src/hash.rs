@@ -0,0 +1,214 @@
+use sha1::{Digest, Sha1};
+use sha2::{Sha256, Sha512};
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::io::{BufReader, Read};
+
+/// Supported hash algorithms, ordered from weakest to strongest.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Algorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl Algorithm {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Sha1 => "sha1",
+            Self::Sha256 => "sha256",
+            Self::Sha512 => "sha512",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "sha1" => Some(Self::Sha1),
+            "sha256" => Some(Self::Sha256),
+            "sha512" => Some(Self::Sha512),
+            _ => None,
+        }
+    }
+
+    fn hex_len(&self) -> usize {
+        match self {
+            Self::Sha1 => 40,
+            Self::Sha256 => 64,
+            Self::Sha512 => 128,
+        }
+    }
+
+    fn from_hex_len(len: usize) -> Option<Self> {
+        match len {
+            40 => Some(Self::Sha1),
+            64 => Some(Self::Sha256),
+            128 => Some(Self::Sha512),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Hash {
+    pub algorithm: Algorithm,
+    pub hex: String,
+}
+
+impl Hash {
+    /// Parses a self-describing hash (`sha256:<hex>`). Hashes without an algorithm prefix are
+    /// identified by their length, as written by `sha1sum` and friends.
+    pub fn parse(s: &str) -> Option<Self> {
+        let (algorithm, hex) = match s.split_once(':') {
+            Some((name, hex)) => (Algorithm::from_name(name)?, hex),
+            None => (Algorithm::from_hex_len(s.len())?, s),
+        };
+
+        let is_valid =
+            hex.len() == algorithm.hex_len() && hex.chars().all(|c| c.is_ascii_hexdigit());
+        is_valid.then(|| Self {
+            algorithm,
+            hex: hex.to_ascii_lowercase(),
+        })
+    }
+}
+
+impl Display for Hash {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}:{}", self.algorithm.name(), self.hex)
+    }
+}
+
+pub enum Hasher {
+    Sha1(Sha1),
+    Sha256(Sha256),
+    Sha512(Sha512),
+}
+
+impl Hasher {
+    pub fn new(algorithm: Algorithm) -> Self {
+        match algorithm {
+            Algorithm::Sha1 => Self::Sha1(Sha1::new()),
+            Algorithm::Sha256 => Self::Sha256(Sha256::new()),
+            Algorithm::Sha512 => Self::Sha512(Sha512::new()),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Sha1(hasher) => hasher.update(data),
+            Self::Sha256(hasher) => hasher.update(data),
+            Self::Sha512(hasher) => hasher.update(data),
+        }
+    }
+
+    pub fn update_reader<R: Read>(&mut self, reader: R) -> std::io::Result<()> {
+        let mut reader = BufReader::new(reader);
+        let mut buffer = [0; 1024];
+        loop {
+            let count = reader.read(&mut buffer)?;
+            if count == 0 {
+                return Ok(());
+            }
+            self.update(&buffer[..count]);
+        }
+    }
+
+    pub fn finalize(self) -> Hash {
+        let (algorithm, hex) = match self {
+            Self::Sha1(hasher) => (Algorithm::Sha1, hex::encode(hasher.finalize())),
+            Self::Sha256(hasher) => (Algorithm::Sha256, hex::encode(hasher.finalize())),
+            Self::Sha512(hasher) => (Algorithm::Sha512, hex::encode(hasher.finalize())),
+        };
+        Hash { algorithm, hex }
+    }
+}
+
+pub fn digest(path: &std::path::Path, algorithm: Algorithm) -> std::io::Result<Hash> {
+    let mut hasher = Hasher::new(algorithm);
+    hasher.update_reader(std::fs::File::open(path)?)?;
+    Ok(hasher.finalize())
+}
+
+/// Returns the strongest of the given hashes.
+pub fn strongest(hashes: &[Hash]) -> Option<&Hash> {
+    hashes.iter().max_by_key(|hash| hash.algorithm)
+}
+
+/// Checks the file at `path` against the strongest of the `expected` hashes.
+pub fn verify(path: &std::path::Path, expected: &[Hash]) -> bool {
+    let Some(expected) = strongest(expected) else {
+        return false;
+    };
+
+    digest(path, expected.algorithm).is_ok_and(|actual| actual == *expected)
+}
+
+/// Parses a list of self-describing hashes, e.g. hashes pinned in the launcher.
+pub fn parse_list(hashes: &[&str]) -> Vec<Hash> {
+    hashes.iter().filter_map(|hash| Hash::parse(hash)).collect()
+}
+
+/// Compares the hash of a downloaded file `name` with the one it was expected to have.
+pub fn check(name: &str, expected: &Hash, actual: &Hash) -> Result<(), IntegrityError> {
+    match expected == actual {
+        true => Ok(()),
+        false => Err(IntegrityError::new(name, expected, actual)),
+    }
+}
+
+pub struct IntegrityError {
+    message: String,
+}
+
+impl IntegrityError {
+    fn new(name: &str, expected: &Hash, actual: &Hash) -> Self {
+        Self {
+            message: format!(
+                "Integrity verification of {name} failed:\nexpected {expected}\ngot {actual}"
+            ),
+        }
+    }
+}
+
+impl Display for IntegrityError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Debug for IntegrityError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for IntegrityError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHA1: &str = "a94a8fe5ccb19ba61c4c0873d391e987982fbbd3";
+    const SHA256: &str = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+
+    #[test]
+    fn parse_prefixed() {
+        let hash = Hash::parse(format!("SHA256:{}", SHA256.to_uppercase()).as_str()).unwrap();
+        assert!(hash.algorithm == Algorithm::Sha256);
+        assert_eq!(hash.hex, SHA256);
+    }
+
+    #[test]
+    fn parse_unprefixed_by_length() {
+        assert!(Hash::parse(SHA1).unwrap().algorithm == Algorithm::Sha1);
+        assert!(Hash::parse(SHA256).unwrap().algorithm == Algorithm::Sha256);
+    }
+
+    #[test]
+    fn parse_rejects_invalid() {
+        assert!(Hash::parse(format!("sha256:{SHA1}").as_str()).is_none());
+        assert!(Hash::parse(format!("md5:{SHA1}").as_str()).is_none());
+        assert!(Hash::parse(&SHA1[1..]).is_none());
+        assert!(Hash::parse(SHA1.replace('a', "g").as_str()).is_none());
+    }
+}
@@ -0,0 +1,669 @@
+use crate::hash::{self, Hash};
+use crate::log;
+use curl::easy::{Easy2, Handler, List, WriteError};
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::io::{Read, Write};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+/// Time a request for release information, like a release index or its hashes, may take in
+/// total. The game waits for these, so they also connect and stall within it, whatever the
+/// download settings say.
+pub const INFO_TIMEOUT: Duration = Duration::from_secs(3);
+/// Additional attempts of a request for release information after a transient failure.
+const INFO_RETRIES: u32 = 1;
+
+pub struct Settings {
+    /// Additional attempts after a transient failure.
+    pub retries: u32,
+    /// Transfers that don't receive anything for this long are aborted.
+    pub stall_timeout: Duration,
+    /// Proxy URL, e.g. `http://proxy:3128` or `socks5h://proxy:1080`.
+    pub proxy: Option<String>,
+    /// Comma separated hosts that are contacted directly.
+    pub no_proxy: Option<String>,
+}
+
+impl Default for Settings {
+    /// The proxy defaults to the one of the environment.
+    fn default() -> Self {
+        Self {
+            retries: 3,
+            stall_timeout: Duration::from_secs(30),
+            proxy: get_env_value(&[
+                "HTTPS_PROXY",
+                "https_proxy",
+                "ALL_PROXY",
+                "all_proxy",
+                "HTTP_PROXY",
+                "http_proxy",
+            ]),
+            no_proxy: get_env_value(&["NO_PROXY", "no_proxy"]),
+        }
+    }
+}
+
+fn get_env_value(names: &[&str]) -> Option<String> {
+    names
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+}
+
+/// Sets the settings of all requests. Only the first call has an effect and requests made before
+/// use the defaults.
+pub fn configure(settings: Settings) {
+    _ = SETTINGS.set(settings);
+}
+
+fn settings() -> &'static Settings {
+    SETTINGS.get_or_init(Settings::default)
+}
+
+/// Progress of a download. Totals are unknown if the server doesn't send a Content-Length.
+#[derive(Clone, Default)]
+pub struct ProgressEvent {
+    pub downloaded: u64,
+    pub total: Option<u64>,
+    /// Bytes per second over the last moments.
+    pub speed: f64,
+    /// Bytes per second since the download started.
+    pub average_speed: f64,
+    pub eta: Option<Duration>,
+}
+
+impl ProgressEvent {
+    pub fn percentage(&self) -> Option<f64> {
+        self.total
+            .filter(|total| *total > 0)
+            .map(|total| self.downloaded as f64 / total as f64 * 100.0)
+    }
+}
+
+pub trait Progress {
+    /// Called once before each file download, retries included.
+    fn start(&self) {}
+
+    fn progress(&self, _event: &ProgressEvent) -> bool {
+        true
+    }
+}
+
+pub struct ProgressCallback {
+    callback: Box<dyn Fn(&ProgressEvent) -> bool + 'static>,
+    start_callback: Option<Box<dyn Fn() + 'static>>,
+}
+
+impl ProgressCallback {
+    pub fn new<F>(callback: F) -> Self
+    where
+        F: Fn(&ProgressEvent) -> bool + 'static,
+    {
+        Self {
+            callback: Box::new(callback),
+            start_callback: None,
+        }
+    }
+
+    pub fn on_start<F>(mut self, start_callback: F) -> Self
+    where
+        F: Fn() + 'static,
+    {
+        self.start_callback = Some(Box::new(start_callback));
+        self
+    }
+}
+
+impl Progress for ProgressCallback {
+    fn start(&self) {
+        if let Some(start_callback) = self.start_callback.as_ref() {
+            start_callback()
+        }
+    }
+
+    fn progress(&self, event: &ProgressEvent) -> bool {
+        self.callback.as_ref()(event)
+    }
+}
+
+pub struct DummyProgress;
+impl Progress for DummyProgress {}
+
+/// Turns curl's byte counts into progress events.
+struct ProgressTracker {
+    started: Instant,
+    sample_time: Instant,
+    sample_downloaded: u64,
+    speed: f64,
+}
+
+impl ProgressTracker {
+    /// Window over which the instantaneous speed is measured.
+    const SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+
+    fn new() -> Self {
+        let now = Instant::now();
+        Self {
+            started: now,
+            sample_time: now,
+            sample_downloaded: 0,
+            speed: 0.0,
+        }
+    }
+
+    /// `downloaded` and `total` don't include the `offset` of a resumed download, which is only
+    /// added to the reported sizes.
+    fn update(&mut self, downloaded: u64, total: Option<u64>, offset: u64) -> ProgressEvent {
+        let now = Instant::now();
+        let sample_elapsed = now - self.sample_time;
+        if sample_elapsed >= Self::SAMPLE_INTERVAL {
+            let sample_speed = downloaded.saturating_sub(self.sample_downloaded) as f64
+                / sample_elapsed.as_secs_f64();
+            // Smooth out bursts a little
+            self.speed = match self.sample_downloaded {
+                0 => sample_speed,
+                _ => self.speed * 0.5 + sample_speed * 0.5,
+            };
+            self.sample_time = now;
+            self.sample_downloaded = downloaded;
+        }
+
+        let elapsed = (now - self.started).as_secs_f64();
+        let average_speed = match elapsed > 0.0 {
+            true => downloaded as f64 / elapsed,
+            false => 0.0,
+        };
+        let eta = total.filter(|_| average_speed > 0.0).map(|total| {
+            Duration::from_secs_f64(total.saturating_sub(downloaded) as f64 / average_speed)
+        });
+
+        ProgressEvent {
+            downloaded: downloaded + offset,
+            total: total.map(|total| total + offset),
+            speed: self.speed,
+            average_speed,
+            eta,
+        }
+    }
+}
+
+struct FileCollector<'a, P> {
+    file: std::fs::File,
+    path: std::path::PathBuf,
+    hasher: Option<hash::Hasher>,
+    progress: &'a P,
+    resume_from: u64,
+    validator_path: std::path::PathBuf,
+    status: u32,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    started: bool,
+    tracker: ProgressTracker,
+}
+
+impl<'a, P: Progress> FileCollector<'a, P> {
+    pub fn new(
+        path: &std::path::Path,
+        algorithm: Option<hash::Algorithm>,
+        progress: &'a P,
+        resume_from: u64,
+    ) -> std::io::Result<Self> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+
+        Ok(Self {
+            file,
+            path: path.to_path_buf(),
+            hasher: algorithm.map(hash::Hasher::new),
+            progress,
+            resume_from,
+            validator_path: get_validator_path(path),
+            status: 0,
+            etag: None,
+            last_modified: None,
+            started: false,
+            tracker: ProgressTracker::new(),
+        })
+    }
+
+    fn is_resumed(&self) -> bool {
+        self.resume_from > 0 && self.status == 206
+    }
+
+    /// Strong ETag or Last-Modified date of the response, usable in an `If-Range` header.
+    fn validator(&self) -> Option<&String> {
+        self.etag
+            .as_ref()
+            .filter(|etag| !etag.starts_with("W/"))
+            .or(self.last_modified.as_ref())
+    }
+
+    /// Called once the body starts: truncates the file unless the server accepted the range and
+    /// stores the validator for resuming an interrupted download. The hash of a resumed download
+    /// starts with the part already on disk.
+    fn begin(&mut self) -> std::io::Result<()> {
+        if !self.is_resumed() {
+            self.resume_from = 0;
+            self.file.set_len(0)?;
+        } else if let Some(hasher) = self.hasher.as_mut() {
+            let downloaded = std::fs::File::open(&self.path)?.take(self.resume_from);
+            hasher.update_reader(downloaded)?;
+        }
+
+        match self.validator() {
+            Some(validator) => std::fs::write(&self.validator_path, validator),
+            None => match std::fs::remove_file(&self.validator_path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            },
+        }
+    }
+}
+
+impl<'a, P: Progress> Handler for FileCollector<'a, P> {
+    fn write(&mut self, data: &[u8]) -> Result<usize, WriteError> {
+        if !self.started {
+            self.started = true;
+            if self.begin().is_err() {
+                return Ok(0);
+            }
+        }
+
+        if self.file.write_all(data).is_err() {
+            return Ok(0);
+        }
+        if let Some(hasher) = self.hasher.as_mut() {
+            hasher.update(data);
+        }
+        Ok(data.len())
+    }
+
+    fn header(&mut self, data: &[u8]) -> bool {
+        let Ok(line) = std::str::from_utf8(data) else {
+            return true;
+        };
+
+        // Every response, including redirects, starts with a status line
+        if line.starts_with("HTTP/") {
+            self.status = line
+                .split_whitespace()
+                .nth(1)
+                .and_then(|code| code.parse().ok())
+                .unwrap_or(0);
+            self.etag = None;
+            self.last_modified = None;
+        } else if let Some((name, value)) = line.split_once(':') {
+            let value = Some(value.trim().to_string());
+            if name.eq_ignore_ascii_case("etag") {
+                self.etag = value;
+            } else if name.eq_ignore_ascii_case("last-modified") {
+                self.last_modified = value;
+            }
+        }
+        true
+    }
+
+    fn progress(&mut self, dltotal: f64, dlnow: f64, _ultotal: f64, _ulnow: f64) -> bool {
+        let offset = match self.is_resumed() {
+            true => self.resume_from,
+            false => 0,
+        };
+        let total = (dltotal > 0.0).then_some(dltotal as u64);
+        let event = self.tracker.update(dlnow as u64, total, offset);
+
+        self.progress.progress(&event)
+    }
+}
+
+/// Downloads `url` to `path`. A partial file left behind by an interrupted download is resumed
+/// with a range request if the server still serves the same content, which is also how failed
+/// attempts are retried.
+pub fn download_file<P: Progress>(
+    url: &str,
+    path: &std::path::Path,
+    progress: &P,
+) -> anyhow::Result<()> {
+    progress.start();
+    with_retries(url, settings().retries, || {
+        download_file_attempt(url, path, None, progress)
+    })?;
+    Ok(())
+}
+
+/// Like `download_file`, also returning the `algorithm` hash of the file computed while it was
+/// downloaded.
+pub fn download_file_hashed<P: Progress>(
+    url: &str,
+    path: &std::path::Path,
+    algorithm: hash::Algorithm,
+    progress: &P,
+) -> anyhow::Result<Hash> {
+    progress.start();
+    let hash = with_retries(url, settings().retries, || {
+        download_file_attempt(url, path, Some(algorithm), progress)
+    })?;
+    Ok(hash.ok_or(DownloadError::NoHash)?)
+}
+
+fn download_file_attempt<P: Progress>(
+    url: &str,
+    path: &std::path::Path,
+    algorithm: Option<hash::Algorithm>,
+    progress: &P,
+) -> anyhow::Result<Option<Hash>> {
+    let validator_path = get_validator_path(path);
+    let validator = std::fs::read_to_string(&validator_path)
+        .ok()
+        .filter(|_| url.starts_with("http"));
+    let resume_from = match validator {
+        Some(_) => path.metadata().map_or(0, |metadata| metadata.len()),
+        None => 0,
+    };
+
+    let mut easy = build_easy_get(
+        url,
+        FileCollector::new(path, algorithm, progress, resume_from)?,
+        None,
+    )?;
+    easy.fail_on_error(true)?;
+    if let (Some(validator), true) = (validator, resume_from > 0) {
+        let mut headers = List::new();
+        headers.append(format!("If-Range: {validator}").as_str())?;
+        easy.http_headers(headers)?;
+        // Not resume_from(), which fails if the server answers with the full content
+        easy.range(format!("{resume_from}-").as_str())?;
+    }
+
+    let result = perform(&mut easy);
+    if resume_from > 0 && easy.response_code()? == 416 {
+        // The partial file doesn't fit the requested content anymore, start over
+        drop(easy);
+        std::fs::remove_file(path)?;
+        std::fs::remove_file(&validator_path).ok();
+        return download_file_attempt(url, path, algorithm, progress);
+    }
+    result?;
+
+    // An empty body never starts the collector
+    let collector = easy.get_mut();
+    if !collector.started {
+        collector.begin()?;
+    }
+
+    std::fs::remove_file(&validator_path).ok();
+    Ok(collector.hasher.take().map(|hasher| hasher.finalize()))
+}
+
+/// Removes a partial download together with its resume information.
+pub fn discard_download(path: &std::path::Path) {
+    std::fs::remove_file(path).ok();
+    std::fs::remove_file(get_validator_path(path)).ok();
+}
+
+fn get_validator_path(path: &std::path::Path) -> std::path::PathBuf {
+    let mut validator_path = path.as_os_str().to_os_string();
+    validator_path.push(".validator");
+    std::path::PathBuf::from(validator_path)
+}
+
+struct Collector {
+    data: Vec<u8>,
+    headers: Vec<(String, String)>,
+}
+
+impl Collector {
+    pub fn new() -> Self {
+        Self {
+            data: Vec::new(),
+            headers: Vec::new(),
+        }
+    }
+}
+
+impl Handler for Collector {
+    fn write(&mut self, data: &[u8]) -> Result<usize, WriteError> {
+        self.data.extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn header(&mut self, data: &[u8]) -> bool {
+        let Ok(line) = std::str::from_utf8(data) else {
+            return true;
+        };
+
+        // Only keep the headers of the last response when following redirects
+        if line.starts_with("HTTP/") {
+            self.headers.clear();
+        } else if let Some((name, value)) = line.split_once(':') {
+            self.headers
+                .push((name.trim().to_string(), value.trim().to_string()));
+        }
+        true
+    }
+}
+
+pub struct Response {
+    pub status: u32,
+    pub body: Vec<u8>,
+    headers: Vec<(String, String)>,
+}
+
+impl Response {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header_name, _)| header_name.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Sends a GET request with additional `headers` and returns the response whatever its status.
+pub fn get(url: &str, headers: &[String], timeout: Option<Duration>) -> anyhow::Result<Response> {
+    let mut easy = build_easy_get(url, Collector::new(), timeout)?;
+    let mut header_list = List::new();
+    for header in headers {
+        header_list.append(header.as_str())?;
+    }
+    easy.http_headers(header_list)?;
+    easy.perform()?;
+
+    let status = easy.response_code()?;
+    let handler = easy.get_mut();
+    Ok(Response {
+        status,
+        body: std::mem::take(&mut handler.data),
+        headers: std::mem::take(&mut handler.headers),
+    })
+}
+
+pub fn download_str(url: &str, timeout: Option<Duration>) -> anyhow::Result<String> {
+    Ok(String::from_utf8(download_bytes(url, timeout)?)?)
+}
+
+pub fn download_bytes(url: &str, timeout: Option<Duration>) -> anyhow::Result<Vec<u8>> {
+    let retries = match timeout {
+        Some(_) => INFO_RETRIES,
+        None => settings().retries,
+    };
+    with_retries(url, retries, || {
+        let mut easy = build_easy_get(url, Collector::new(), timeout)?;
+        easy.fail_on_error(true)?;
+        perform(&mut easy)?;
+        let handler = easy.get_mut();
+
+        Ok(std::mem::take(&mut handler.data))
+    })
+}
+
+struct NullCollector;
+
+impl Handler for NullCollector {}
+
+/// Follows redirects of `url` without downloading the body and returns the final URL.
+pub fn resolve_url(url: &str, timeout: Option<Duration>) -> anyhow::Result<String> {
+    let mut easy = build_easy_get(url, NullCollector, timeout)?;
+    easy.nobody(true)?;
+    easy.perform()?;
+
+    Ok(easy.effective_url()?.unwrap_or(url).to_string())
+}
+
+/// Requests with a `timeout` connect, stall and finish within it, others may stall for the
+/// configured stall timeout.
+fn build_easy_get<H: Handler>(
+    url: &str,
+    handler: H,
+    timeout: Option<Duration>,
+) -> Result<Easy2<H>, curl::Error> {
+    let mut easy = Easy2::new(handler);
+    easy.get(true)?;
+    easy.follow_location(true)?;
+    easy.url(url)?;
+    // TODO: consider using a user agent designated for this cod4 launcher
+    easy.useragent("curl/8.9.1")?;
+    easy.progress(true)?;
+    let stall_timeout = timeout.unwrap_or(settings().stall_timeout);
+    if let Some(timeout) = timeout {
+        easy.timeout(timeout)?;
+    }
+    easy.connect_timeout(stall_timeout)?;
+    easy.low_speed_limit(1)?;
+    easy.low_speed_time(stall_timeout)?;
+    if let Some(proxy) = settings().proxy.as_ref() {
+        easy.proxy(proxy)?;
+    }
+    if let Some(no_proxy) = settings().no_proxy.as_ref() {
+        easy.noproxy(no_proxy)?;
+    }
+    Ok(easy)
+}
+
+/// Performs the request, reporting HTTP errors of `fail_on_error` with their status code.
+fn perform<H>(easy: &mut Easy2<H>) -> anyhow::Result<()> {
+    match easy.perform() {
+        Err(e) if e.is_http_returned_error() => Err(StatusError::new(easy.response_code()?).into()),
+        result => Ok(result?),
+    }
+}
+
+/// Runs `attempt` until it succeeds, fails permanently or `retries` are used up, waiting
+/// exponentially longer between attempts.
+fn with_retries<T, F>(url: &str, retries: u32, mut attempt: F) -> anyhow::Result<T>
+where
+    F: FnMut() -> anyhow::Result<T>,
+{
+    let mut retry = 0;
+    loop {
+        match attempt() {
+            Err(e) if retry < retries && is_transient(&e) => {
+                retry += 1;
+                let delay = get_backoff_delay(retry);
+                log::write(
+                    format!(
+                        "Attempt {retry} of {url} failed: {e}, retrying in {} ms",
+                        delay.as_millis()
+                    )
+                    .as_str(),
+                );
+                std::thread::sleep(delay);
+            }
+            Err(e) => {
+                log::write(format!("Request of {url} failed: {e}").as_str());
+                return Err(e);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Whether a request failed because the server doesn't have the requested file.
+pub fn is_not_found(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<StatusError>()
+        .is_some_and(|e| e.status == 404)
+}
+
+/// Network failures, stalls and server errors may go away, anything else won't. Transfers
+/// cancelled by the user are never retried.
+fn is_transient(e: &anyhow::Error) -> bool {
+    if let Some(e) = e.downcast_ref::<StatusError>() {
+        return e.status >= 500;
+    }
+
+    e.downcast_ref::<curl::Error>().is_some_and(|e| {
+        e.is_couldnt_resolve_proxy()
+            || e.is_couldnt_resolve_host()
+            || e.is_couldnt_connect()
+            || e.is_operation_timedout()
+            || e.is_partial_file()
+            || e.is_got_nothing()
+            || e.is_send_error()
+            || e.is_recv_error()
+            || e.is_ssl_connect_error()
+            || e.is_http2_error()
+            || e.is_http2_stream_error()
+    })
+}
+
+/// 0.5s doubled per retry up to 8s, randomized by ±50% so clients don't retry in lockstep.
+fn get_backoff_delay(retry: u32) -> Duration {
+    let delay = Duration::from_millis(500) * 2u32.pow(retry.clamp(1, 5) - 1);
+    let jitter = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.subsec_nanos() % 1000);
+    delay * (500 + jitter) / 1000
+}
+
+struct StatusError {
+    status: u32,
+}
+
+impl StatusError {
+    fn new(status: u32) -> Self {
+        Self { status }
+    }
+}
+
+impl Display for StatusError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "The server responded with HTTP status {}", self.status)
+    }
+}
+
+impl Debug for StatusError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "The server responded with HTTP status {}", self.status)
+    }
+}
+
+impl Error for StatusError {}
+
+pub enum DownloadError {
+    NoHash,
+}
+
+impl DownloadError {
+    fn message(&self) -> &str {
+        match self {
+            Self::NoHash => "The download produced no hash",
+        }
+    }
+}
+
+impl Display for DownloadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl Debug for DownloadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl Error for DownloadError {}
@@ -0,0 +1,113 @@
+use crate::hash::{self, Hash};
+use crate::http;
+use crate::manifest::Artifact;
+use crate::release::{self, AssetInformation, ReleaseInformation};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::path::{Path, PathBuf};
+
+/// Artifact whose installed file is missing or doesn't match the release.
+pub struct PendingArtifact<'a> {
+    pub artifact: &'a Artifact,
+    pub asset: &'a AssetInformation,
+    pub path: PathBuf,
+}
+
+/// Whether the file at `path` matches the release. Without a published hash only a missing file
+/// can be detected.
+pub fn is_installed(path: &Path, expected_hashes: Option<&[Hash]>) -> bool {
+    match expected_hashes {
+        Some(expected_hashes) => hash::verify(path, expected_hashes),
+        None => path.exists(),
+    }
+}
+
+/// Returns the artifacts of `release` that need to be installed below `root`. Artifacts the
+/// release has no asset for are skipped.
+pub fn find_pending<'a>(
+    release_info: &'a ReleaseInformation,
+    artifacts: &'a [Artifact],
+    hashes: &HashMap<&str, Vec<Hash>>,
+    root: &Path,
+) -> Result<Vec<PendingArtifact<'a>>, InstallError> {
+    let mut pending = Vec::new();
+    for artifact in artifacts {
+        let Some(asset) = release::find_asset(release_info, artifact.pattern.as_str()) else {
+            continue;
+        };
+        let path = artifact
+            .path(root, asset.name.as_str())
+            .ok_or(InstallError::AssetName)?;
+
+        let expected_hashes = artifact.expected_hashes(asset.name.as_str(), hashes);
+        if !is_installed(path.as_path(), expected_hashes.as_deref()) {
+            pending.push(PendingArtifact {
+                artifact,
+                asset,
+                path,
+            });
+        }
+    }
+
+    Ok(pending)
+}
+
+/// Downloads `asset` to `path`. The file is only replaced if the download matches the strongest
+/// of `expected_hashes`.
+pub fn download_verified<P: http::Progress>(
+    asset: &AssetInformation,
+    path: &Path,
+    expected_hashes: &[Hash],
+    progress: &P,
+) -> anyhow::Result<()> {
+    let expected_hash = hash::strongest(expected_hashes).ok_or(InstallError::HashNotFound)?;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let download_path = path.with_extension("part");
+    let actual_hash = http::download_file_hashed(
+        asset.url.as_str(),
+        download_path.as_path(),
+        expected_hash.algorithm,
+        progress,
+    )?;
+
+    if let Err(e) = hash::check(asset.name.as_str(), expected_hash, &actual_hash) {
+        http::discard_download(download_path.as_path());
+        return Err(e.into());
+    }
+
+    std::fs::rename(download_path, path)?;
+    Ok(())
+}
+
+pub enum InstallError {
+    AssetName,
+    HashNotFound,
+}
+
+impl InstallError {
+    fn message(&self) -> &str {
+        match self {
+            Self::AssetName => "Unexpected asset name",
+            Self::HashNotFound => "Couldn't find the asset hash",
+        }
+    }
+}
+
+impl Display for InstallError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl Debug for InstallError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl Error for InstallError {}
@@ -0,0 +1,17 @@
+//! Release discovery, download and verification shared by the launcher and the command-line
+//! updater. Nothing in here depends on Windows.
+
+pub mod delta;
+pub mod github;
+pub mod hash;
+pub mod http;
+pub mod install;
+pub mod local;
+pub mod log;
+pub mod manifest;
+pub mod markdown;
+pub mod mirror;
+pub mod release;
+pub mod signature;
+pub mod source;
+pub mod time;
@@ -0,0 +1,68 @@
+use crate::release::{self, Channel, ParseError, ReleaseInformation};
+use crate::source::ReleaseSource;
+use semver::Version;
+use std::path::{Path, PathBuf};
+
+const INDEX_FILE_NAME: &str = "release.json";
+
+/// Release stored in a local directory: a `release.json` index in the mirror format next to the
+/// assets it references. Assets are downloaded through `file://` URLs.
+pub struct LocalDirectorySource {
+    path: PathBuf,
+}
+
+impl LocalDirectorySource {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+        }
+    }
+
+    fn read_index(&self) -> anyhow::Result<json::Value> {
+        let index = std::fs::read_to_string(self.path.join(INDEX_FILE_NAME))
+            .map_err(|_| ParseError::FetchError)?;
+        Ok(json::from_str(index.as_str()).map_err(|_| ParseError::InvalidResponse)?)
+    }
+}
+
+impl ReleaseSource for LocalDirectorySource {
+    fn name(&self) -> String {
+        format!("local directory ({})", self.path.display())
+    }
+
+    fn fetch_release_information(&self, channel: Channel) -> anyhow::Result<ReleaseInformation> {
+        Ok(release::parse_releases(
+            &self.read_index()?,
+            Some(file_url(self.path.as_path()).as_str()),
+            channel,
+        )?)
+    }
+
+    fn fetch_release_by_version(&self, version: &Version) -> anyhow::Result<ReleaseInformation> {
+        Ok(release::find_release(
+            &self.read_index()?,
+            Some(file_url(self.path.as_path()).as_str()),
+            version,
+        )?)
+    }
+}
+
+fn file_url(path: &Path) -> String {
+    let path = std::path::absolute(path).unwrap_or(path.to_path_buf());
+    let mut url = String::from("file://");
+    let path = path.to_string_lossy().replace('\\', "/");
+    if !path.starts_with('/') {
+        url.push('/');
+    }
+
+    for c in path.chars() {
+        match c {
+            ' ' => url.push_str("%20"),
+            '#' => url.push_str("%23"),
+            '%' => url.push_str("%25"),
+            '?' => url.push_str("%3F"),
+            _ => url.push(c),
+        }
+    }
+    url
+}
@@ -0,0 +1,69 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The log is moved to `<name>.old` once it grows beyond this size.
+const MAX_LOG_SIZE: u64 = 1024 * 1024;
+
+static LOG_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Sets the file messages are appended to. Messages written before are dropped.
+pub fn init(path: &Path) {
+    if let Ok(mut log_path) = LOG_PATH.lock() {
+        *log_path = Some(path.to_path_buf());
+    }
+}
+
+/// Appends a timestamped line to the log file. Failing to log is never an error.
+pub fn write(message: &str) {
+    let Ok(log_path) = LOG_PATH.lock() else {
+        return;
+    };
+    let Some(path) = log_path.as_deref() else {
+        return;
+    };
+
+    if path
+        .metadata()
+        .is_ok_and(|metadata| metadata.len() > MAX_LOG_SIZE)
+    {
+        let mut old_path = path.as_os_str().to_os_string();
+        old_path.push(".old");
+        _ = std::fs::rename(path, old_path);
+    }
+
+    if let Ok(mut file) = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+    {
+        _ = writeln!(file, "[{}] {message}", format_utc_time(SystemTime::now()));
+    }
+}
+
+fn format_utc_time(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    let (days, seconds) = (seconds / 86400, seconds % 86400);
+
+    // Civil date from days since the epoch, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719468;
+    let era = z / 146097;
+    let day_of_era = z % 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02} UTC",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
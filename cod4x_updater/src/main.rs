@@ -0,0 +1,219 @@
+use cod4x_updater::github::GitHubSource;
+use cod4x_updater::install::{self, InstallError};
+use cod4x_updater::local::LocalDirectorySource;
+use cod4x_updater::mirror::MirrorSource;
+use cod4x_updater::release::{self, Channel, SignaturePolicy};
+use cod4x_updater::source::ReleaseSource;
+use cod4x_updater::{http, log, manifest, signature};
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: cod4x_updater <check|install> --source <source> --dir <directory> [options]
+
+Checks or installs the artifacts of the newest release into <directory>, laid out like
+%LOCALAPPDATA%\\CallofDuty4MW.
+
+Sources:
+  github:<owner>/<repository>  GitHub releases
+  http(s)://.../release.json   Mirror index
+  <path>                       Local directory holding a release.json
+
+Options:
+  --channel <channel>  stable (default), beta or nightly
+  --manifest <file>    Artifact manifest to use instead of the one of the release
+  --log <file>         Append failed download attempts to <file>
+  --public-key <hex>   Verify releases against this Ed25519 key instead of the embedded one";
+
+const GITHUB_TOKEN_VARIABLE: &str = "COD4X_GITHUB_TOKEN";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Command {
+    /// List the artifacts that are missing or outdated.
+    Check,
+    /// Download and install the artifacts that are missing or outdated.
+    Install,
+}
+
+struct Options {
+    command: Command,
+    source: String,
+    dir: PathBuf,
+    channel: Channel,
+    manifest: Option<PathBuf>,
+    log: Option<PathBuf>,
+    public_key: Option<String>,
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{e}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    match run(&options) {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Options, UsageError> {
+    let mut args = args.iter();
+
+    let command = match args.next().map(|arg| arg.as_str()) {
+        Some("check") => Command::Check,
+        Some("install") => Command::Install,
+        Some(command) => return Err(UsageError::new(format!("Unknown command {command}"))),
+        None => return Err(UsageError::new("Missing command".to_string())),
+    };
+
+    let mut source = None;
+    let mut dir = None;
+    let mut channel = Channel::Stable;
+    let mut manifest = None;
+    let mut log = None;
+    let mut public_key = None;
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| UsageError::new(format!("Missing value for {arg}")))?;
+        match arg.as_str() {
+            "--source" => source = Some(value.clone()),
+            "--dir" => dir = Some(PathBuf::from(value)),
+            "--channel" => {
+                channel = Channel::from_name(value)
+                    .ok_or_else(|| UsageError::new(format!("Unknown channel {value}")))?
+            }
+            "--manifest" => manifest = Some(PathBuf::from(value)),
+            "--log" => log = Some(PathBuf::from(value)),
+            "--public-key" => public_key = Some(value.clone()),
+            _ => return Err(UsageError::new(format!("Unknown option {arg}"))),
+        }
+    }
+
+    Ok(Options {
+        command,
+        source: source.ok_or_else(|| UsageError::new("Missing --source".to_string()))?,
+        dir: dir.ok_or_else(|| UsageError::new("Missing --dir".to_string()))?,
+        channel,
+        manifest,
+        log,
+        public_key,
+    })
+}
+
+fn create_source(source: &str) -> Box<dyn ReleaseSource> {
+    if let Some(repository) = source.strip_prefix("github:") {
+        let token = std::env::var(GITHUB_TOKEN_VARIABLE)
+            .ok()
+            .filter(|token| !token.is_empty());
+        return Box::new(GitHubSource::new(repository, None, token.as_deref()));
+    }
+
+    match source.starts_with("http://") || source.starts_with("https://") {
+        true => Box::new(MirrorSource::new(source)),
+        false => Box::new(LocalDirectorySource::new(Path::new(source))),
+    }
+}
+
+fn run(options: &Options) -> anyhow::Result<()> {
+    if let Some(log_path) = options.log.as_deref() {
+        log::init(log_path);
+    }
+    if let Some(public_key) = options.public_key.as_deref() {
+        signature::set_public_key(public_key);
+    }
+
+    let source = create_source(options.source.as_str());
+    let release_information = source.fetch_release_information(options.channel)?;
+    println!(
+        "Using release {} from {}",
+        release_information.tag_name,
+        source.name()
+    );
+
+    let hashes = release::fetch_hashes(&release_information, &SignaturePolicy::Always)?;
+    let hashes = release::parse_hashes(hashes.as_str());
+    let artifacts = match options.manifest.as_deref() {
+        Some(manifest_path) => manifest::parse(std::fs::read_to_string(manifest_path)?.as_str())?,
+        None => manifest::fetch(&release_information, &hashes)?,
+    };
+
+    let pending = install::find_pending(
+        &release_information,
+        &artifacts,
+        &hashes,
+        options.dir.as_path(),
+    )?;
+    if pending.is_empty() {
+        println!("All artifacts are up to date");
+        return Ok(());
+    }
+
+    for pending_artifact in &pending {
+        println!(
+            "{} needs to be installed: {}",
+            pending_artifact.artifact.display_name,
+            pending_artifact.path.display()
+        );
+    }
+    if let Some(notes) = release_information.notes() {
+        println!("\n{notes}\n");
+    }
+    if options.command == Command::Check {
+        return Ok(());
+    }
+
+    for pending_artifact in &pending {
+        let asset = pending_artifact.asset;
+        let expected_hashes = pending_artifact
+            .artifact
+            .expected_hashes(asset.name.as_str(), &hashes)
+            .ok_or(InstallError::HashNotFound)?;
+
+        println!("Downloading {}...", asset.name);
+        install::download_verified(
+            asset,
+            pending_artifact.path.as_path(),
+            &expected_hashes,
+            &http::DummyProgress,
+        )?;
+    }
+    println!("Installed {} artifact(s)", pending.len());
+
+    Ok(())
+}
+
+struct UsageError {
+    message: String,
+}
+
+impl UsageError {
+    fn new(message: String) -> Self {
+        Self { message }
+    }
+}
+
+impl Display for UsageError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Debug for UsageError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for UsageError {}
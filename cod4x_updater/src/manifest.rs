@@ -0,0 +1,249 @@
+use crate::hash::{self, Hash};
+use crate::http;
+use crate::release::ReleaseInformation;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::path::{Component, Path, PathBuf};
+
+const MANIFEST_ASSET_NAME: &str = "artifacts.json";
+
+/// Manifest used for releases that don't publish their own.
+const DEFAULT_MANIFEST: &str = include_str!("artifacts.json");
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ArtifactType {
+    /// Versioned CoD4x DLL, installed into its own `cod4x_<version>` directory.
+    Module,
+    File,
+}
+
+/// Release asset the launcher installs.
+pub struct Artifact {
+    /// Regex matching the asset name.
+    pub pattern: String,
+    pub display_name: String,
+    pub artifact_type: ArtifactType,
+    /// Directory relative to the installation root, the CoD4 appdata directory for the launcher.
+    pub destination: PathBuf,
+    /// Hash overriding the one in `hashes.txt`.
+    pub hash: Option<Hash>,
+    pub requires_elevate: bool,
+    pub requires_restart: bool,
+    /// Whether the player may not deselect updates of this artifact.
+    pub mandatory: bool,
+}
+
+impl Artifact {
+    /// Installed location of the asset `asset_name` below `root`. Modules are kept in a
+    /// directory per version.
+    pub fn path(&self, root: &Path, asset_name: &str) -> Option<PathBuf> {
+        let destination_dir = root.join(&self.destination);
+
+        match self.artifact_type {
+            ArtifactType::Module => Some(
+                destination_dir
+                    .join(Path::new(asset_name).file_stem()?)
+                    .join(asset_name),
+            ),
+            ArtifactType::File => Some(destination_dir.join(asset_name)),
+        }
+    }
+
+    /// Hashes the asset `asset_name` of this artifact is expected to have.
+    pub fn expected_hashes(
+        &self,
+        asset_name: &str,
+        hashes: &HashMap<&str, Vec<Hash>>,
+    ) -> Option<Vec<Hash>> {
+        match self.hash.as_ref() {
+            Some(hash) => Some(vec![hash.clone()]),
+            None => hashes.get(asset_name).cloned(),
+        }
+    }
+}
+
+/// Returns the artifacts of the manifest published with `release`, or of the default manifest.
+/// A published manifest is only trusted if it matches its hash in the signed `hashes.txt`.
+pub fn fetch(
+    release: &ReleaseInformation,
+    hashes: &HashMap<&str, Vec<Hash>>,
+) -> anyhow::Result<Vec<Artifact>> {
+    let Some(manifest_asset) = release
+        .assets
+        .iter()
+        .find(|asset| asset.name == MANIFEST_ASSET_NAME)
+    else {
+        return Ok(parse(DEFAULT_MANIFEST)?);
+    };
+
+    let expected_hash = hashes
+        .get(MANIFEST_ASSET_NAME)
+        .and_then(|expected_hashes| hash::strongest(expected_hashes))
+        .ok_or(ManifestError::HashNotFound)?;
+
+    let manifest = http::download_bytes(manifest_asset.url.as_str(), Some(http::INFO_TIMEOUT))?;
+    let mut hasher = hash::Hasher::new(expected_hash.algorithm);
+    hasher.update(manifest.as_slice());
+    hash::check(MANIFEST_ASSET_NAME, expected_hash, &hasher.finalize())?;
+
+    Ok(parse(String::from_utf8(manifest)?.as_str())?)
+}
+
+pub fn parse(manifest_str: &str) -> Result<Vec<Artifact>, ManifestError> {
+    let manifest_json: json::Value =
+        json::from_str(manifest_str).map_err(|_| ManifestError::InvalidJson)?;
+
+    manifest_json
+        .pointer("/artifacts")
+        .and_then(|artifacts_json| artifacts_json.as_array())
+        .ok_or(ManifestError::Artifacts)?
+        .iter()
+        .map(parse_artifact)
+        .collect()
+}
+
+fn parse_artifact(artifact_json: &json::Value) -> Result<Artifact, ManifestError> {
+    let get_str = |pointer: &str| {
+        artifact_json
+            .pointer(pointer)
+            .and_then(|value| value.as_str())
+    };
+    let get_bool = |pointer: &str| {
+        artifact_json
+            .pointer(pointer)
+            .and_then(|value| value.as_bool())
+            .unwrap_or(false)
+    };
+
+    let pattern = get_str("/pattern").ok_or(ManifestError::Artifacts)?;
+    if regex::Regex::new(pattern).is_err() {
+        return Err(ManifestError::Pattern);
+    }
+
+    let artifact_type = match get_str("/type").unwrap_or("file") {
+        "module" => ArtifactType::Module,
+        "file" => ArtifactType::File,
+        _ => return Err(ManifestError::ArtifactType),
+    };
+
+    let destination = Path::new(get_str("/destination").ok_or(ManifestError::Destination)?);
+    // Artifacts must stay inside the appdata directory
+    if !destination
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return Err(ManifestError::Destination);
+    }
+
+    let hash = match get_str("/hash") {
+        Some(hash) => Some(Hash::parse(hash).ok_or(ManifestError::Hash)?),
+        None => None,
+    };
+
+    Ok(Artifact {
+        pattern: pattern.to_string(),
+        display_name: get_str("/display_name").unwrap_or(pattern).to_string(),
+        artifact_type,
+        destination: destination.to_path_buf(),
+        hash,
+        requires_elevate: get_bool("/requires_elevate"),
+        requires_restart: get_bool("/requires_restart"),
+        mandatory: get_bool("/mandatory"),
+    })
+}
+
+/// Finds the artifact an update was created for.
+pub fn find_artifact<'a>(artifacts: &'a [Artifact], pattern: &str) -> Option<&'a Artifact> {
+    artifacts
+        .iter()
+        .find(|artifact| artifact.pattern == pattern)
+}
+
+pub enum ManifestError {
+    InvalidJson,
+    Artifacts,
+    Pattern,
+    ArtifactType,
+    Destination,
+    Hash,
+    HashNotFound,
+}
+
+impl ManifestError {
+    fn message(&self) -> &str {
+        match self {
+            Self::InvalidJson => "Invalid JSON in the artifact manifest",
+            Self::Artifacts => "Invalid artifact manifest entry",
+            Self::Pattern => "Invalid artifact name pattern",
+            Self::ArtifactType => "Unknown artifact type",
+            Self::Destination => "Invalid artifact destination",
+            Self::Hash => "Invalid artifact hash",
+            Self::HashNotFound => "Couldn't find the artifact manifest hash",
+        }
+    }
+}
+
+impl Display for ManifestError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl Debug for ManifestError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl Error for ManifestError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_default_manifest() {
+        let artifacts = parse(DEFAULT_MANIFEST).unwrap();
+        assert!(artifacts[0].artifact_type == ArtifactType::Module);
+        assert_eq!(artifacts[0].destination, Path::new("bin"));
+        assert!(artifacts[1..]
+            .iter()
+            .all(|artifact| artifact.artifact_type == ArtifactType::File));
+    }
+
+    #[test]
+    fn parse_artifact_fields() {
+        let manifest = r#"{ "artifacts": [{
+            "pattern": "^iw3mp\\.exe$",
+            "destination": "tools/bin",
+            "hash": "sha256:9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08",
+            "requires_elevate": true,
+            "mandatory": true
+        }] }"#;
+
+        let artifact = &parse(manifest).unwrap()[0];
+        assert_eq!(artifact.display_name, artifact.pattern);
+        assert!(artifact.artifact_type == ArtifactType::File);
+        assert!(artifact.hash.is_some());
+        assert!(artifact.requires_elevate && artifact.mandatory && !artifact.requires_restart);
+    }
+
+    #[test]
+    fn parse_rejects_invalid_entries() {
+        let parse_artifact =
+            |artifact: &str| parse(format!(r#"{{ "artifacts": [{artifact}] }}"#).as_str());
+
+        assert!(parse("[]").is_err());
+        assert!(parse_artifact(r#"{ "destination": "main" }"#).is_err());
+        assert!(parse_artifact(r#"{ "pattern": "(", "destination": "main" }"#).is_err());
+        assert!(
+            parse_artifact(r#"{ "pattern": "a", "type": "dir", "destination": "main" }"#).is_err()
+        );
+        assert!(parse_artifact(r#"{ "pattern": "a", "destination": "../main" }"#).is_err());
+        assert!(parse_artifact(r#"{ "pattern": "a", "destination": "/main" }"#).is_err());
+        assert!(
+            parse_artifact(r#"{ "pattern": "a", "destination": "main", "hash": "abc" }"#).is_err()
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use regex::Regex;
+
+/// Reduces Markdown, as used in release notes, to readable plain text. Formatting is dropped,
+/// links keep their text and list items their bullet.
+pub fn to_text(markdown: &str) -> String {
+    let inline_rules = [
+        // Images and links
+        (r"!?\[([^\]]*)\]\([^)]*\)", "$1"),
+        (r"<(https?://[^>]+)>", "$1"),
+        // HTML tags and comments
+        (r"<!--.*?-->|</?[a-zA-Z][^>]*>", ""),
+        (r"\*\*(.+?)\*\*|__(.+?)__", "$1$2"),
+        (r"\*([^*\s][^*]*)\*", "$1"),
+        (r"~~(.+?)~~", "$1"),
+        (r"`([^`]*)`", "$1"),
+    ]
+    .map(|(pattern, replacement)| {
+        (
+            Regex::new(pattern).expect("Failed to compile regex"),
+            replacement,
+        )
+    });
+
+    let mut lines = Vec::new();
+    let mut in_code_block = false;
+    for line in markdown.lines() {
+        let line = line.trim_end();
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            lines.push(format!("    {line}"));
+            continue;
+        }
+
+        let mut text = strip_block(line);
+        for (regex, replacement) in &inline_rules {
+            text = regex.replace_all(text.as_str(), *replacement).into_owned();
+        }
+        lines.push(text.trim_end().to_string());
+    }
+
+    // Keep at most one blank line between paragraphs
+    let mut text = String::new();
+    let mut blank = true;
+    for line in lines {
+        let is_blank = line.trim().is_empty();
+        if is_blank && blank {
+            continue;
+        }
+        blank = is_blank;
+        text.push_str(line.as_str());
+        text.push('\n');
+    }
+
+    text.trim_end().to_string()
+}
+
+fn strip_block(line: &str) -> String {
+    let content = line.trim_start();
+    let indent = &line[..line.len() - content.len()];
+
+    let rule = content.replace(' ', "");
+    let is_rule = rule.len() >= 3
+        && ['-', '*', '_']
+            .iter()
+            .any(|c| rule.chars().all(|r| r == *c));
+    if is_rule {
+        return String::new();
+    }
+
+    if content.starts_with('#') {
+        return content.trim_start_matches('#').trim().to_string();
+    }
+    if let Some(quote) = content.strip_prefix('>') {
+        return quote.trim_start().to_string();
+    }
+    for bullet in ["- ", "* ", "+ "] {
+        if let Some(item) = content.strip_prefix(bullet) {
+            let item = item
+                .strip_prefix("[ ] ")
+                .or_else(|| item.strip_prefix("[x] "))
+                .unwrap_or(item);
+            return format!("{indent}- {item}");
+        }
+    }
+
+    line.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_text_strips_formatting() {
+        let markdown = "\
+## Changes
+
+
+- [x] Fixed a **crash** in `Sys_Main`
+* See [the README](https://example.com/README.md)
+> Quoted <b>text</b>
+---
+```
+let x = 1;
+```";
+
+        assert_eq!(
+            to_text(markdown),
+            "\
+Changes
+
+- Fixed a crash in Sys_Main
+- See the README
+Quoted text
+
+    let x = 1;"
+        );
+    }
+
+    #[test]
+    fn to_text_keeps_nested_lists_and_identifiers() {
+        assert_eq!(
+            to_text("- a\n  - *b* ~~c~~ snake_case_name"),
+            "- a\n  - b c snake_case_name"
+        );
+    }
+}
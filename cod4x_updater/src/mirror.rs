@@ -0,0 +1,53 @@
+use crate::http;
+use crate::release::{self, Channel, ParseError, ReleaseInformation};
+use crate::source::ReleaseSource;
+use semver::Version;
+
+/// Release index served as a static JSON file, e.g. `https://mirror.example.com/cod4x/release.json`.
+/// Relative asset URLs are resolved against the directory of the index.
+pub struct MirrorSource {
+    index_url: String,
+}
+
+impl MirrorSource {
+    pub fn new(index_url: &str) -> Self {
+        Self {
+            index_url: index_url.to_string(),
+        }
+    }
+
+    fn fetch_index(&self) -> anyhow::Result<json::Value> {
+        let response = http::download_str(self.index_url.as_str(), Some(http::INFO_TIMEOUT))
+            .map_err(|_| ParseError::FetchError)?;
+        Ok(json::from_str(response.as_str()).map_err(|_| ParseError::InvalidResponse)?)
+    }
+
+    fn base_url(&self) -> &str {
+        match self.index_url.rsplit_once('/') {
+            Some((base_url, _)) if base_url.contains("://") => base_url,
+            _ => self.index_url.as_str(),
+        }
+    }
+}
+
+impl ReleaseSource for MirrorSource {
+    fn name(&self) -> String {
+        format!("mirror ({})", self.index_url)
+    }
+
+    fn fetch_release_information(&self, channel: Channel) -> anyhow::Result<ReleaseInformation> {
+        Ok(release::parse_releases(
+            &self.fetch_index()?,
+            Some(self.base_url()),
+            channel,
+        )?)
+    }
+
+    fn fetch_release_by_version(&self, version: &Version) -> anyhow::Result<ReleaseInformation> {
+        Ok(release::find_release(
+            &self.fetch_index()?,
+            Some(self.base_url()),
+            version,
+        )?)
+    }
+}
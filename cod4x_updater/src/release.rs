@@ -0,0 +1,498 @@
+use crate::hash::Hash;
+use crate::http;
+use crate::log;
+use crate::markdown;
+use crate::signature::{self, SignatureError};
+use regex::Regex;
+use semver::Version;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+
+/// Marks security releases in release names or notes, e.g. on GitHub.
+const SECURITY_MARKER: &str = "[security]";
+
+const HASHES_ASSET_NAME: &str = "hashes.txt";
+const SIGNATURE_ASSET_NAME: &str = "hashes.txt.sig";
+
+pub struct AssetInformation {
+    pub name: String,
+    pub url: String,
+    pub size: Option<u64>,
+}
+
+pub struct ReleaseInformation {
+    pub tag_name: String,
+    pub name: Option<String>,
+    /// Publish date as given by the source, e.g. `2025-03-01T12:00:00Z`.
+    pub published_at: Option<String>,
+    /// Release notes in Markdown.
+    pub body: Option<String>,
+    /// Security releases are flagged with `"security": true` or a `[security]` marker in their
+    /// name or notes. Their updates can't be deferred.
+    pub security: bool,
+    pub assets: Vec<AssetInformation>,
+    /// Name of the release source the information was fetched from.
+    pub source: String,
+}
+
+impl ReleaseInformation {
+    /// Release name, publish date and notes as plain text, if the source provides notes.
+    pub fn notes(&self) -> Option<String> {
+        let body = markdown::to_text(self.body.as_deref()?);
+        if body.is_empty() {
+            return None;
+        }
+
+        let title = self.name.as_deref().unwrap_or(self.tag_name.as_str());
+        // Only the date part of the timestamp
+        Some(
+            match self.published_at.as_deref().and_then(|date| date.get(..10)) {
+                Some(date) => format!("{title} ({date})\n{body}"),
+                None => format!("{title}\n{body}"),
+            },
+        )
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    Stable,
+    Beta,
+    Nightly,
+}
+
+impl Channel {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "stable" => Some(Self::Stable),
+            "beta" => Some(Self::Beta),
+            "nightly" => Some(Self::Nightly),
+            _ => None,
+        }
+    }
+
+    /// Stable only takes full releases, beta adds prereleases and nightly also takes prereleases
+    /// tagged as nightly builds. Drafts are never taken.
+    fn accepts(&self, release_json: &json::Value) -> bool {
+        let is_set = |pointer: &str| {
+            release_json
+                .pointer(pointer)
+                .and_then(|value| value.as_bool())
+                .unwrap_or(false)
+        };
+        let is_nightly = release_json
+            .pointer("/tag_name")
+            .and_then(|tag_name| tag_name.as_str())
+            .is_some_and(|tag_name| tag_name.to_ascii_lowercase().contains("nightly"));
+
+        if is_set("/draft") {
+            return false;
+        }
+
+        match self {
+            Self::Stable => !is_set("/prerelease"),
+            Self::Beta => !is_nightly,
+            Self::Nightly => true,
+        }
+    }
+}
+
+/// Picks the newest release of `channel` from a list of releases in the GitHub API format, which
+/// is ordered from newest to oldest. A single release object is accepted as well.
+pub fn parse_releases(
+    releases_json: &json::Value,
+    base_url: Option<&str>,
+    channel: Channel,
+) -> Result<ReleaseInformation, ParseError> {
+    let release_json = match releases_json.as_array() {
+        Some(releases_json) => releases_json
+            .iter()
+            .find(|release_json| channel.accepts(release_json)),
+        None => Some(releases_json).filter(|release_json| channel.accepts(release_json)),
+    };
+
+    parse_release(release_json.ok_or(ParseError::NoRelease)?, base_url)
+}
+
+/// Picks the release tagged with `version` from a list of releases in the GitHub API format. A
+/// single release object is accepted as well. Drafts are never taken.
+pub fn find_release(
+    releases_json: &json::Value,
+    base_url: Option<&str>,
+    version: &Version,
+) -> Result<ReleaseInformation, ParseError> {
+    let is_version = |release_json: &&json::Value| {
+        Channel::Nightly.accepts(release_json)
+            && release_json
+                .pointer("/tag_name")
+                .and_then(|tag_name| tag_name.as_str())
+                .and_then(|tag_name| parse_version(tag_name).ok())
+                .is_some_and(|release_version| release_version == *version)
+    };
+    let release_json = match releases_json.as_array() {
+        Some(releases_json) => releases_json.iter().find(is_version),
+        None => Some(releases_json).filter(is_version),
+    };
+
+    parse_release(release_json.ok_or(ParseError::NoRelease)?, base_url)
+}
+
+/// Parses a release tag into a version, completing tags like `21.1` or `v21.1-beta` to full
+/// semantic versions.
+pub fn parse_version(tag_name: &str) -> Result<Version, semver::Error> {
+    let tag_name = tag_name.trim_start_matches(['v', 'V']);
+    let (core, suffix) = match tag_name.find(['-', '+']) {
+        Some(position) => tag_name.split_at(position),
+        None => (tag_name, ""),
+    };
+
+    let mut version = core.to_string();
+    for _ in core.matches('.').count()..2 {
+        version.push_str(".0");
+    }
+    version.push_str(suffix);
+    Version::parse(version.as_str())
+}
+
+/// Parses a release object in the GitHub API format.
+///
+/// Static mirrors and local directories publish the same shape, so asset URLs may also be given
+/// as `url` and relative to `base_url`.
+fn parse_release(
+    release_json: &json::Value,
+    base_url: Option<&str>,
+) -> Result<ReleaseInformation, ParseError> {
+    let tag_name = release_json
+        .pointer("/tag_name")
+        .ok_or(ParseError::TagName)?
+        .as_str()
+        .ok_or(ParseError::TagName)?;
+
+    let release_assets_json = release_json
+        .pointer("/assets")
+        .ok_or(ParseError::ReleaseAssets)?
+        .as_array()
+        .ok_or(ParseError::ReleaseAssets)?;
+
+    let mut assets = Vec::<AssetInformation>::new();
+    for asset_json in release_assets_json {
+        let asset_name = asset_json
+            .pointer("/name")
+            .ok_or(ParseError::ReleaseAssets)?
+            .as_str()
+            .ok_or(ParseError::ReleaseAssets)?;
+
+        let asset_url = asset_json
+            .pointer("/browser_download_url")
+            .or_else(|| asset_json.pointer("/url"))
+            .ok_or(ParseError::ReleaseAssets)?
+            .as_str()
+            .ok_or(ParseError::ReleaseAssets)?;
+
+        let asset_size = asset_json.pointer("/size").and_then(|size| size.as_u64());
+
+        assets.push(AssetInformation {
+            name: asset_name.to_string(),
+            url: resolve_url(base_url, asset_url),
+            size: asset_size,
+        });
+    }
+
+    let get_str = |pointer: &str| {
+        release_json
+            .pointer(pointer)
+            .and_then(|value| value.as_str())
+            .filter(|value| !value.trim().is_empty())
+            .map(|value| value.to_string())
+    };
+
+    let name = get_str("/name");
+    let body = get_str("/body");
+    let security = release_json
+        .pointer("/security")
+        .and_then(|security| security.as_bool())
+        .unwrap_or_else(|| {
+            [name.as_deref(), body.as_deref()]
+                .into_iter()
+                .flatten()
+                .any(|text| text.to_ascii_lowercase().contains(SECURITY_MARKER))
+        });
+
+    Ok(ReleaseInformation {
+        tag_name: tag_name.to_string(),
+        name,
+        published_at: get_str("/published_at"),
+        body,
+        security,
+        assets,
+        source: String::new(),
+    })
+}
+
+fn resolve_url(base_url: Option<&str>, url: &str) -> String {
+    match base_url {
+        Some(base_url) if !url.contains("://") => {
+            format!("{}/{}", base_url.trim_end_matches('/'), url)
+        }
+        _ => url.to_string(),
+    }
+}
+
+pub fn find_asset<'a>(
+    release_info: &'a ReleaseInformation,
+    pattern: &str,
+) -> Option<&'a AssetInformation> {
+    let regex = Regex::new(pattern).expect("Failed to compile regex");
+    release_info
+        .assets
+        .iter()
+        .find(|asset| regex.is_match(asset.name.as_str()))
+}
+
+/// Which releases of a source must be signed. Sources that didn't sign their releases from the
+/// start can't reject their older, unsigned releases.
+pub enum SignaturePolicy {
+    /// Every release must be signed.
+    Always,
+    /// Releases newer than the version, the last unsigned one, must be signed.
+    After(Version),
+}
+
+impl SignaturePolicy {
+    fn requires_signature(&self, tag_name: &str) -> bool {
+        match self {
+            Self::Always => true,
+            Self::After(cutoff) => {
+                parse_version(tag_name).map_or(true, |version| version > *cutoff)
+            }
+        }
+    }
+}
+
+/// Downloads `hashes.txt` of the release and verifies its detached signature `hashes.txt.sig`.
+/// The returned hashes are only trusted once the signature has been verified, unless `policy`
+/// grandfathers the release as unsigned.
+pub fn fetch_hashes(
+    release_info: &ReleaseInformation,
+    policy: &SignaturePolicy,
+) -> anyhow::Result<String> {
+    let find_asset_named = |name: &str| release_info.assets.iter().find(|asset| asset.name == name);
+    let hashes_asset = match find_asset_named(HASHES_ASSET_NAME) {
+        None => return Err(HashesError::AssetNotFound.into()),
+        Some(hashes_asset) => hashes_asset,
+    };
+    let signature_asset = find_asset_named(SIGNATURE_ASSET_NAME);
+    if signature_asset.is_none() && policy.requires_signature(release_info.tag_name.as_str()) {
+        return Err(SignatureError::Missing.into());
+    }
+
+    let hashes = http::download_bytes(hashes_asset.url.as_str(), Some(http::INFO_TIMEOUT))
+        .map_err(|_| HashesError::FetchError)?;
+    // Sources that can't list the assets offer the signature whether it was published or not
+    let signature = match signature_asset {
+        Some(signature_asset) => {
+            match http::download_bytes(signature_asset.url.as_str(), Some(http::INFO_TIMEOUT)) {
+                Ok(signature) => Some(signature),
+                Err(e) if http::is_not_found(&e) => None,
+                Err(_) => return Err(HashesError::SignatureFetchError.into()),
+            }
+        }
+        None => None,
+    };
+    match signature {
+        Some(signature) => signature::verify(&hashes, &signature)?,
+        None if policy.requires_signature(release_info.tag_name.as_str()) => {
+            return Err(SignatureError::Missing.into())
+        }
+        None => log::write(
+            format!(
+                "Release {} from {} is not signed",
+                release_info.tag_name, release_info.source
+            )
+            .as_str(),
+        ),
+    }
+
+    Ok(String::from_utf8(hashes).map_err(|_| HashesError::FetchError)?)
+}
+
+/// Parses a hash manifest with `<hash> <filename>` lines. Hashes describe their algorithm
+/// (`sha256:<hex>`) and a file may be listed once per algorithm; lines with unknown algorithms
+/// are ignored.
+pub fn parse_hashes(s: &str) -> HashMap<&str, Vec<Hash>> {
+    let mut map = HashMap::<&str, Vec<Hash>>::new();
+
+    for line in s.lines() {
+        if let Some((hash, filename)) = line
+            .split_once(char::is_whitespace)
+            .map(|(h, f)| (h, f.trim_start().trim_start_matches('*').trim_end()))
+        {
+            if let Some(hash) = Hash::parse(hash) {
+                map.entry(filename).or_default().push(hash);
+            }
+        }
+    }
+
+    map
+}
+
+pub enum ParseError {
+    FetchError,
+    InvalidResponse,
+    TagName,
+    ReleaseAssets,
+    NoRelease,
+}
+
+impl ParseError {
+    fn message(&self) -> &str {
+        match self {
+            Self::FetchError => "Failed to fetch latest release information",
+            Self::InvalidResponse => "Invalid release information response",
+            Self::TagName => "Couldn't get tag name",
+            Self::ReleaseAssets => "Couldn't get release assets",
+            Self::NoRelease => "Couldn't find a matching release",
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl Debug for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl Error for ParseError {}
+
+pub enum HashesError {
+    AssetNotFound,
+    FetchError,
+    SignatureFetchError,
+}
+
+impl HashesError {
+    fn message(&self) -> &str {
+        match self {
+            Self::AssetNotFound => "Couldn't find hashes.txt asset",
+            Self::FetchError => "Failed to fetch hashes.txt",
+            Self::SignatureFetchError => "Failed to fetch hashes.txt.sig",
+        }
+    }
+}
+
+impl Display for HashesError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl Debug for HashesError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl Error for HashesError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash::Algorithm;
+
+    #[test]
+    fn parse_version_completes_tags() {
+        assert_eq!(parse_version("21").unwrap(), Version::new(21, 0, 0));
+        assert_eq!(parse_version("v21.1").unwrap(), Version::new(21, 1, 0));
+        assert_eq!(
+            parse_version("V21.1-beta.2").unwrap(),
+            Version::parse("21.1.0-beta.2").unwrap()
+        );
+        assert_eq!(
+            parse_version("1.2.3+build").unwrap(),
+            Version::parse("1.2.3+build").unwrap()
+        );
+        assert!(parse_version("latest").is_err());
+    }
+
+    #[test]
+    fn channel_accepts() {
+        let release = json::json!({ "tag_name": "v21.1" });
+        let prerelease = json::json!({ "tag_name": "v21.2-beta", "prerelease": true });
+        let nightly = json::json!({ "tag_name": "nightly-20250301", "prerelease": true });
+        let draft = json::json!({ "tag_name": "v21.3", "draft": true });
+
+        let accepted = |channel: Channel| {
+            [&release, &prerelease, &nightly, &draft].map(|json| channel.accepts(json))
+        };
+        assert_eq!(accepted(Channel::Stable), [true, false, false, false]);
+        assert_eq!(accepted(Channel::Beta), [true, true, false, false]);
+        assert_eq!(accepted(Channel::Nightly), [true, true, true, false]);
+    }
+
+    fn unsigned_release(tag_name: &str, hashes_path: &std::path::Path) -> ReleaseInformation {
+        ReleaseInformation {
+            tag_name: tag_name.to_string(),
+            name: None,
+            published_at: None,
+            body: None,
+            security: false,
+            assets: vec![AssetInformation {
+                name: "hashes.txt".to_string(),
+                url: format!("file://{}", hashes_path.display()),
+                size: None,
+            }],
+            source: String::new(),
+        }
+    }
+
+    #[test]
+    fn fetch_hashes_requires_signature_after_cutoff() {
+        let hashes_path =
+            std::env::temp_dir().join(format!("cod4x_hashes_{}.txt", std::process::id()));
+        std::fs::write(&hashes_path, "hashes").unwrap();
+        let policy = SignaturePolicy::After(Version::new(21, 3, 0));
+
+        let error = fetch_hashes(&unsigned_release("v21.4", &hashes_path), &policy).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<SignatureError>(),
+            Some(SignatureError::Missing)
+        ));
+        let error = fetch_hashes(&unsigned_release("latest", &hashes_path), &policy).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<SignatureError>(),
+            Some(SignatureError::Missing)
+        ));
+        assert_eq!(
+            fetch_hashes(&unsigned_release("v21.3", &hashes_path), &policy).unwrap(),
+            "hashes"
+        );
+
+        std::fs::remove_file(&hashes_path).ok();
+    }
+
+    #[test]
+    fn parse_hashes_formats() {
+        let sha1 = "a94a8fe5ccb19ba61c4c0873d391e987982fbbd3";
+        let sha256 = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+        let hashes = format!(
+            "{sha1}  cod4x_21.dll\nsha256:{sha256} *cod4x_21.dll\r\nmd5:0123 jcod4x_00.iwd\n\n{sha1}\tcod4x_patch.ff\n"
+        );
+
+        let map = parse_hashes(hashes.as_str());
+        let algorithms = map["cod4x_21.dll"]
+            .iter()
+            .map(|hash| hash.algorithm)
+            .collect::<Vec<_>>();
+        assert!(algorithms == [Algorithm::Sha1, Algorithm::Sha256]);
+        assert_eq!(map["cod4x_patch.ff"][0].hex, sha1);
+        assert!(!map.contains_key("jcod4x_00.iwd"));
+        assert_eq!(map.len(), 2);
+    }
+}
@@ -0,0 +1,74 @@
+use ed25519_dalek::{Signature, VerifyingKey};
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::sync::OnceLock;
+
+/// Hex encoded Ed25519 public key the release hash manifests are signed with, embedded at build
+/// time.
+const RELEASE_PUBLIC_KEY: Option<&str> = option_env!("COD4X_RELEASE_PUBLIC_KEY");
+
+/// Public key used instead of the embedded one, e.g. to verify test releases.
+static PUBLIC_KEY_OVERRIDE: OnceLock<String> = OnceLock::new();
+
+/// Verifies releases against the hex encoded `key_hex` instead of the embedded key. Only the
+/// first key set is used.
+pub fn set_public_key(key_hex: &str) {
+    _ = PUBLIC_KEY_OVERRIDE.set(key_hex.to_string());
+}
+
+/// Verifies a detached Ed25519 `signature` of `message` against the embedded release key.
+pub fn verify(message: &[u8], signature: &[u8]) -> Result<(), SignatureError> {
+    let public_key = release_public_key()?;
+    let signature = Signature::from_slice(signature).map_err(|_| SignatureError::Invalid)?;
+
+    public_key
+        .verify_strict(message, &signature)
+        .map_err(|_| SignatureError::Invalid)
+}
+
+fn release_public_key() -> Result<VerifyingKey, SignatureError> {
+    let key_hex = PUBLIC_KEY_OVERRIDE
+        .get()
+        .map(|key_hex| key_hex.as_str())
+        .or(RELEASE_PUBLIC_KEY)
+        .filter(|key_hex| !key_hex.trim().is_empty())
+        .ok_or(SignatureError::NoPublicKey)?;
+    let key_bytes: [u8; 32] = hex::decode(key_hex.trim())
+        .ok()
+        .and_then(|key_bytes| key_bytes.try_into().ok())
+        .ok_or(SignatureError::InvalidPublicKey)?;
+
+    VerifyingKey::from_bytes(&key_bytes).map_err(|_| SignatureError::InvalidPublicKey)
+}
+
+pub enum SignatureError {
+    Missing,
+    Invalid,
+    NoPublicKey,
+    InvalidPublicKey,
+}
+
+impl SignatureError {
+    fn message(&self) -> &str {
+        match self {
+            Self::Missing => "Release hashes are not signed",
+            Self::Invalid => "Invalid release hashes signature",
+            Self::NoPublicKey => "No release public key was embedded at build time",
+            Self::InvalidPublicKey => "Invalid release public key",
+        }
+    }
+}
+
+impl Display for SignatureError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl Debug for SignatureError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl Error for SignatureError {}
@@ -0,0 +1,88 @@
+use crate::release::{Channel, ReleaseInformation};
+use semver::Version;
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+
+pub trait ReleaseSource: Send + Sync {
+    fn name(&self) -> String;
+
+    fn fetch_release_information(&self, channel: Channel) -> anyhow::Result<ReleaseInformation>;
+
+    /// Fetches the release of `version`, e.g. to verify the files of an older installed release.
+    fn fetch_release_by_version(&self, version: &Version) -> anyhow::Result<ReleaseInformation>;
+}
+
+/// Tries each source in turn and returns the first release information fetched successfully.
+pub struct FallbackSource {
+    sources: Vec<Box<dyn ReleaseSource>>,
+}
+
+impl FallbackSource {
+    pub fn new(sources: Vec<Box<dyn ReleaseSource>>) -> Self {
+        Self { sources }
+    }
+}
+
+impl ReleaseSource for FallbackSource {
+    fn name(&self) -> String {
+        self.sources
+            .iter()
+            .map(|source| source.name())
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    fn fetch_release_information(&self, channel: Channel) -> anyhow::Result<ReleaseInformation> {
+        self.fetch_first(|source| source.fetch_release_information(channel))
+    }
+
+    fn fetch_release_by_version(&self, version: &Version) -> anyhow::Result<ReleaseInformation> {
+        self.fetch_first(|source| source.fetch_release_by_version(version))
+    }
+}
+
+impl FallbackSource {
+    fn fetch_first<F>(&self, fetch: F) -> anyhow::Result<ReleaseInformation>
+    where
+        F: Fn(&dyn ReleaseSource) -> anyhow::Result<ReleaseInformation>,
+    {
+        let mut failures = Vec::new();
+        for source in &self.sources {
+            match fetch(source.as_ref()) {
+                Ok(mut release_information) => {
+                    release_information.source = source.name();
+                    return Ok(release_information);
+                }
+                Err(e) => failures.push(format!("{}: {e}", source.name())),
+            }
+        }
+
+        Err(FallbackError::new(failures).into())
+    }
+}
+
+struct FallbackError {
+    message: String,
+}
+
+impl FallbackError {
+    fn new(failures: Vec<String>) -> Self {
+        Self {
+            message: format!("All release sources failed:\n{}", failures.join("\n")),
+        }
+    }
+}
+
+impl Display for FallbackError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Debug for FallbackError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for FallbackError {}
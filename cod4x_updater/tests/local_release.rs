@@ -0,0 +1,59 @@
+//! Installs the signed release in `fixtures/release` the way the command-line updater does.
+
+use cod4x_updater::local::LocalDirectorySource;
+use cod4x_updater::release::{self, Channel, SignaturePolicy};
+use cod4x_updater::source::ReleaseSource;
+use cod4x_updater::{http, install, manifest, signature};
+use std::path::{Path, PathBuf};
+
+/// Public key matching `fixtures/release/hashes.txt.sig`. The private key isn't kept.
+const FIXTURE_PUBLIC_KEY: &str = "2ce244ea586298a8bb658b7e3b1275e67c89e71453c504dc0d4e77ca73a4ee79";
+
+fn fixture_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/release")
+}
+
+#[test]
+fn install_local_release() {
+    signature::set_public_key(FIXTURE_PUBLIC_KEY);
+    let install_dir =
+        std::env::temp_dir().join(format!("cod4x_updater_test_{}", std::process::id()));
+    _ = std::fs::remove_dir_all(&install_dir);
+
+    let source = LocalDirectorySource::new(fixture_dir().as_path());
+    let release_information = source.fetch_release_information(Channel::Stable).unwrap();
+    assert_eq!(release_information.tag_name, "v21.0");
+
+    let hashes = release::fetch_hashes(&release_information, &SignaturePolicy::Always).unwrap();
+    let hashes = release::parse_hashes(hashes.as_str());
+    let artifacts = manifest::fetch(&release_information, &hashes).unwrap();
+    let find_pending =
+        || install::find_pending(&release_information, &artifacts, &hashes, &install_dir).unwrap();
+
+    let pending = find_pending();
+    assert_eq!(pending.len(), 3);
+    for pending_artifact in &pending {
+        let asset = pending_artifact.asset;
+        let expected_hashes = pending_artifact
+            .artifact
+            .expected_hashes(asset.name.as_str(), &hashes)
+            .unwrap();
+        install::download_verified(
+            asset,
+            pending_artifact.path.as_path(),
+            &expected_hashes,
+            &http::DummyProgress,
+        )
+        .unwrap();
+    }
+    assert!(install_dir.join("bin/cod4x_21/cod4x_21.dll").is_file());
+    assert!(find_pending().is_empty());
+
+    // Modified files are installed again
+    std::fs::write(install_dir.join("zone/cod4x_patch.ff"), "modified").unwrap();
+    let pending = find_pending();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0].asset.name, "cod4x_patch.ff");
+
+    _ = std::fs::remove_dir_all(&install_dir);
+}
@@ -0,0 +1,30 @@
+pub fn get_value<'a>(value_name: &str, cmdline_args: &'a [String]) -> Option<&'a String> {
+    let mut args = cmdline_args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "+set" || arg == "+seta" {
+            if let Some(arg) = args.next() {
+                if arg == value_name {
+                    return args.next();
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Whether the boolean cvar `value_name` is set to 1 on the command line of this process.
+pub fn is_enabled(value_name: &str) -> bool {
+    let cmdline_args: Vec<_> = std::env::args().collect();
+    get_value(value_name, &cmdline_args).is_some_and(|v| v == "1")
+}
+
+/// Parameters for restarting elevated, keeping the mode the launcher was started in.
+pub fn get_elevated_params() -> String {
+    let mut params = "+set elevated 1".to_string();
+    for value_name in ["repair", "silent_update", "auto_elevate"] {
+        if is_enabled(value_name) {
+            params += format!(" +set {value_name} 1").as_str();
+        }
+    }
+    params
+}
@@ -42,6 +42,22 @@ pub fn get_module_version() -> anyhow::Result<Version> {
     }
 }
 
+/// Paths of all installed CoD4x DLLs (`bin/cod4x_<version>/cod4x_<version>.dll`).
+pub fn get_installed_modules() -> anyhow::Result<Vec<std::path::PathBuf>> {
+    let cod4x_dlls = fs::appdata_bin_path()?.join("cod4x_*\\cod4x_*.dll");
+    let cod4x_dlls = cod4x_dlls.to_str().ok_or(CoD4xLoadError::ModuleNotFound)?;
+
+    Ok(glob::glob_with(
+        cod4x_dlls,
+        glob::MatchOptions {
+            case_sensitive: false,
+            ..glob::MatchOptions::new()
+        },
+    )?
+    .flatten()
+    .collect())
+}
+
 fn load_module(version: Option<&String>) -> anyhow::Result<libloading::Library> {
     let cod4x_bin_dir = fs::appdata_bin_path()?;
 
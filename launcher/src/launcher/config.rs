@@ -0,0 +1,285 @@
+use super::filesystem as fs;
+use super::http;
+use super::msg_box;
+use super::updater::release::Channel;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+const CONFIG_FILE_NAME: &str = "launcher.json";
+const GITHUB_TOKEN_VARIABLE: &str = "COD4X_GITHUB_TOKEN";
+const DEFAULT_CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+pub enum SourceConfig {
+    GitHub { repository: String },
+    GitHubDownload { repository: String },
+    Mirror { url: String },
+    Local { path: PathBuf },
+}
+
+/// Launcher settings read from `launcher.json` in the CoD4 appdata directory.
+#[derive(Default)]
+pub struct Config {
+    sources: HashMap<String, Vec<SourceConfig>>,
+    channel: Option<Channel>,
+    channels: HashMap<String, Channel>,
+    github_token: Option<String>,
+    retries: Option<u32>,
+    stall_timeout: Option<Duration>,
+    proxy: Option<String>,
+    no_proxy: Option<String>,
+    silent: bool,
+    auto_elevate: bool,
+    check_interval: Option<Duration>,
+}
+
+impl Config {
+    /// Release sources configured for `component`, in the order they should be tried.
+    pub fn sources(&self, component: &str) -> Option<&[SourceConfig]> {
+        self.sources
+            .get(component)
+            .map(|sources| sources.as_slice())
+    }
+
+    /// Release channel followed by `component`, defaulting to the global channel and then stable.
+    pub fn channel(&self, component: &str) -> Channel {
+        self.channels
+            .get(component)
+            .copied()
+            .or(self.channel)
+            .unwrap_or(Channel::Stable)
+    }
+
+    /// GitHub API token, taken from the `COD4X_GITHUB_TOKEN` environment variable or the
+    /// configuration.
+    pub fn github_token(&self) -> Option<String> {
+        std::env::var(GITHUB_TOKEN_VARIABLE)
+            .ok()
+            .or(self.github_token.clone())
+            .filter(|token| !token.is_empty())
+    }
+
+    pub fn http_settings(&self) -> http::Settings {
+        let defaults = http::Settings::default();
+        http::Settings {
+            retries: self.retries.unwrap_or(defaults.retries),
+            stall_timeout: self.stall_timeout.unwrap_or(defaults.stall_timeout),
+            proxy: self.proxy.clone().or(defaults.proxy),
+            no_proxy: self.no_proxy.clone().or(defaults.no_proxy),
+        }
+    }
+
+    /// Whether updates are installed without asking and without the updater window.
+    pub fn silent_updates(&self) -> bool {
+        self.silent
+    }
+
+    /// Whether silent updates may restart the launcher with administrator rights.
+    pub fn auto_elevate(&self) -> bool {
+        self.auto_elevate
+    }
+
+    /// Minimum time between two update checks, zero to check on every start.
+    pub fn check_interval(&self) -> Duration {
+        self.check_interval.unwrap_or(DEFAULT_CHECK_INTERVAL)
+    }
+}
+
+/// Returns the launcher configuration, loading it on first use. An invalid configuration is
+/// reported once and replaced by the defaults.
+pub fn get() -> &'static Config {
+    static CONFIG: OnceLock<Config> = OnceLock::new();
+
+    CONFIG.get_or_init(|| match load() {
+        Ok(config) => config,
+        Err(e) => {
+            msg_box::message_box(
+                format!("Invalid launcher configuration, using defaults:\n{e}").as_str(),
+                "CoD4x Launcher",
+            );
+            Config::default()
+        }
+    })
+}
+
+fn load() -> anyhow::Result<Config> {
+    let path = fs::get_appdata_cod4_path()?.join(CONFIG_FILE_NAME);
+    if !path.exists() {
+        return Ok(Config::default());
+    }
+
+    let config_str = std::fs::read_to_string(path)?;
+    let config_json: json::Value =
+        json::from_str(config_str.as_str()).map_err(|_| ConfigError::InvalidJson)?;
+
+    let mut sources = HashMap::new();
+    if let Some(sources_json) = config_json.pointer("/sources") {
+        let sources_json = sources_json.as_object().ok_or(ConfigError::Sources)?;
+        for (component, source_json) in sources_json {
+            // A single source or an ordered list of fallbacks
+            let component_sources = match source_json.as_array() {
+                Some(sources_json) => sources_json
+                    .iter()
+                    .map(parse_source)
+                    .collect::<Result<Vec<_>, _>>()?,
+                None => vec![parse_source(source_json)?],
+            };
+            if component_sources.is_empty() {
+                return Err(ConfigError::Sources.into());
+            }
+            sources.insert(component.clone(), component_sources);
+        }
+    }
+
+    let channel = config_json
+        .pointer("/channel")
+        .map(parse_channel)
+        .transpose()?;
+
+    let mut channels = HashMap::new();
+    if let Some(channels_json) = config_json.pointer("/channels") {
+        let channels_json = channels_json.as_object().ok_or(ConfigError::Channel)?;
+        for (component, channel_json) in channels_json {
+            channels.insert(component.clone(), parse_channel(channel_json)?);
+        }
+    }
+
+    let github_token = config_json
+        .pointer("/github_token")
+        .map(|token_json| {
+            token_json
+                .as_str()
+                .map(|token| token.to_string())
+                .ok_or(ConfigError::GitHubToken)
+        })
+        .transpose()?;
+
+    let get_http_u64 = |pointer: &str| {
+        config_json
+            .pointer(pointer)
+            .map(|value| value.as_u64().ok_or(ConfigError::Http))
+            .transpose()
+    };
+    let retries = get_http_u64("/http/retries")?
+        .map(|retries| u32::try_from(retries).map_err(|_| ConfigError::Http))
+        .transpose()?;
+    let stall_timeout = get_http_u64("/http/stall_timeout")?
+        .filter(|seconds| *seconds > 0)
+        .map(Duration::from_secs);
+    let get_http_str = |pointer: &str| {
+        config_json
+            .pointer(pointer)
+            .map(|value| {
+                value
+                    .as_str()
+                    .map(|value| value.to_string())
+                    .ok_or(ConfigError::Http)
+            })
+            .transpose()
+    };
+    let proxy = get_http_str("/http/proxy")?;
+    let no_proxy = get_http_str("/http/no_proxy")?;
+
+    let get_updates_bool = |pointer: &str| {
+        config_json
+            .pointer(pointer)
+            .map(|value| value.as_bool().ok_or(ConfigError::Updates))
+            .transpose()
+            .map(|value| value.unwrap_or(false))
+    };
+    let silent = get_updates_bool("/updates/silent")?;
+    let auto_elevate = get_updates_bool("/updates/auto_elevate")?;
+    let check_interval = config_json
+        .pointer("/updates/check_interval")
+        .map(|value| value.as_u64().ok_or(ConfigError::Updates))
+        .transpose()?
+        .map(Duration::from_secs);
+
+    Ok(Config {
+        sources,
+        channel,
+        channels,
+        github_token,
+        retries,
+        stall_timeout,
+        proxy,
+        no_proxy,
+        silent,
+        auto_elevate,
+        check_interval,
+    })
+}
+
+fn parse_channel(channel_json: &json::Value) -> Result<Channel, ConfigError> {
+    channel_json
+        .as_str()
+        .and_then(Channel::from_name)
+        .ok_or(ConfigError::Channel)
+}
+
+fn parse_source(source_json: &json::Value) -> Result<SourceConfig, ConfigError> {
+    let get_str = |pointer: &str| {
+        source_json
+            .pointer(pointer)
+            .and_then(|value| value.as_str())
+            .map(|value| value.to_string())
+            .ok_or(ConfigError::Sources)
+    };
+
+    match get_str("/type")?.as_str() {
+        "github" => Ok(SourceConfig::GitHub {
+            repository: get_str("/repository")?,
+        }),
+        "github-download" => Ok(SourceConfig::GitHubDownload {
+            repository: get_str("/repository")?,
+        }),
+        "mirror" => Ok(SourceConfig::Mirror {
+            url: get_str("/url")?,
+        }),
+        "local" => Ok(SourceConfig::Local {
+            path: PathBuf::from(get_str("/path")?),
+        }),
+        _ => Err(ConfigError::SourceType),
+    }
+}
+
+enum ConfigError {
+    InvalidJson,
+    Sources,
+    SourceType,
+    Channel,
+    GitHubToken,
+    Http,
+    Updates,
+}
+
+impl ConfigError {
+    fn message(&self) -> &str {
+        match self {
+            Self::InvalidJson => "Invalid JSON in launcher.json",
+            Self::Sources => "Invalid release source entry",
+            Self::SourceType => "Unknown release source type",
+            Self::Channel => "Unknown release channel, expected stable, beta or nightly",
+            Self::GitHubToken => "Invalid GitHub token",
+            Self::Http => "Invalid HTTP settings",
+            Self::Updates => "Invalid update settings",
+        }
+    }
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl Debug for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl Error for ConfigError {}
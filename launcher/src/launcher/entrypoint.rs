@@ -6,30 +6,51 @@ use winapi::um::processthreadsapi::{CreateThread, ExitThread};
 use cod4v17_patch::patch;
 
 use core::ffi::{c_char, c_void};
+use std::time::Duration;
 
+use super::cmdline;
 use super::cod4x;
+use super::config;
 use super::filesystem as fs;
+use super::http;
 use super::iw3mp;
+use super::log;
 use super::miles32;
 use super::module;
 use super::msg_box::*;
+use super::process;
 use super::updater;
 
 extern "system" fn run(hinstance: LPVOID) -> DWORD {
     let hinstance = hinstance as HINSTANCE;
+
     let cmdline_args: Vec<_> = std::env::args().collect();
-    let legacy_arg = get_cmdline_value("legacymode", &cmdline_args);
+    let legacy_arg = cmdline::get_value("legacymode", &cmdline_args);
     let run_legacy = legacy_arg.is_some_and(|v| v == "1");
     if !run_legacy {
-        let version = get_cmdline_value("protocolversion", &cmdline_args);
+        let version = cmdline::get_value("protocolversion", &cmdline_args);
         if version.is_none() {
-            let elevated_arg = get_cmdline_value("elevated", &cmdline_args);
+            let elevated_arg = cmdline::get_value("elevated", &cmdline_args);
             let elevated = elevated_arg.is_some_and(|v| v == "1");
-            if let Err(e) = updater::run_updater(elevated) {
+            let mode = match cmdline::is_enabled("repair") {
+                true => updater::Mode::Repair,
+                false => updater::Mode::Update,
+            };
+            let options = updater::Options {
+                mode,
+                silent: is_silent(),
+                auto_elevate: cmdline::is_enabled("auto_elevate") || config::get().auto_elevate(),
+                check_interval: match cmdline::is_enabled("force_update_check") {
+                    true => Duration::ZERO,
+                    false => config::get().check_interval(),
+                },
+            };
+            if let Err(e) = updater::run_updater(elevated, options) {
                 message_box(format!("Failed to run updater: {e}").as_str(), "Error");
             }
         }
 
+        updater::finish_boot();
         match cod4x::run(hinstance, version) {
             Err(e) => {
                 message_box(format!("{e}").as_str(), "Error");
@@ -46,6 +67,7 @@ extern "system" fn run(hinstance: LPVOID) -> DWORD {
         );
     }
 
+    updater::finish_boot();
     iw3mp::startup() as DWORD
 }
 
@@ -66,6 +88,38 @@ fn run_thread(hinstance: HINSTANCE) {
     }
 }
 
+/// Silent updates only show errors, everything else goes to the log.
+fn is_silent() -> bool {
+    cmdline::is_enabled("silent_update") || config::get().silent_updates()
+}
+
+fn notify(message: &str, silent: bool) {
+    match silent {
+        true => log::write(message),
+        false => message_box(message, "CoD4x Launcher"),
+    }
+}
+
+/// Asks the player to start the game again after fixing a file. Silent updates restart it right
+/// away, or leave that to the instance waiting for this elevated one.
+fn finish_fix(message: &str, silent: bool, elevated: bool) -> ! {
+    if !silent {
+        message_box(
+            format!("{message}\nYou can restart the game now.").as_str(),
+            "CoD4x Launcher",
+        );
+        std::process::exit(0);
+    }
+
+    log::write(message);
+    if !elevated {
+        if let Err(e) = process::restart(process::Privileges::User, None) {
+            message_box(format!("{e}").as_str(), "CoD4x Launcher");
+        }
+    }
+    std::process::exit(0);
+}
+
 #[no_mangle]
 #[allow(non_snake_case)]
 extern "C" fn StartLauncher(
@@ -74,24 +128,32 @@ extern "C" fn StartLauncher(
     mss32importnames: *const *const c_char,
     mss32importcount: i32,
 ) {
+    updater::begin_boot();
+    // Before the configuration, whose errors would block the self-test on a message box
+    updater::answer_self_test();
+
     let module_path = module::get_path();
     if let Some(install_dir) = module_path.parent() {
         fs::set_current_directory(install_dir);
     }
 
+    if let Ok(appdata_path) = fs::get_appdata_cod4_path() {
+        log::init(appdata_path.join("launcher.log").as_path());
+    }
+    http::configure(config::get().http_settings());
+
     let cmdline_args: Vec<_> = std::env::args().collect();
-    let elevated_arg = get_cmdline_value("elevated", &cmdline_args);
+    let elevated_arg = cmdline::get_value("elevated", &cmdline_args);
     let elevated = elevated_arg.is_some_and(|v| v == "1");
 
+    let silent = is_silent();
+
     if !iw3mp::is_pure() || !iw3mp::is_large_address_aware() {
         if !elevated {
-            message_box(
-                "Impure iw3mp.exe detected.\nAttempting to fix...",
-                "CoD4x Launcher",
-            );
+            notify("Impure iw3mp.exe detected.\nAttempting to fix...", silent);
         }
         fs::disable_directory_virtualization();
-        if let Err(e) = iw3mp::replace_module() {
+        if let Err(e) = iw3mp::replace_module(silent) {
             message_box(
                 format!(
                     "Failed to replace iw3mp.exe: {e}\n\n \
@@ -101,27 +163,27 @@ extern "C" fn StartLauncher(
                 .as_str(),
                 "CoD4x Launcher",
             );
-        } else {
-            message_box(
-                "Successfully fixed iw3mp.exe.\nYou can restart the game now.",
-                "CoD4x Launcher",
-            );
+            std::process::exit(1);
         }
-        std::process::exit(0);
+        finish_fix("Successfully fixed iw3mp.exe.", silent, elevated);
     }
 
-    let _miles32 = match miles32::load_module(mss32importprocs, mss32importnames, mss32importcount)
-    {
+    // A damaged miles32.dll may still load, so it's only verified when repairing
+    let miles32 = match cmdline::is_enabled("repair") && !miles32::is_pure() {
+        true => Err(miles32::Miles32LoadError::Impure),
+        false => miles32::load_module(mss32importprocs, mss32importnames, mss32importcount),
+    };
+    let _miles32 = match miles32 {
         Ok(lib) => lib,
         Err(e) => {
             if !elevated {
-                message_box(
+                notify(
                     format!("Failed to load miles32.dll: {e}\nAttempting to fix...").as_str(),
-                    "CoD4x Launcher",
+                    silent,
                 );
             }
             fs::disable_directory_virtualization();
-            if let Err(e) = miles32::replace_module() {
+            if let Err(e) = miles32::replace_module(silent) {
                 message_box(
                     format!(
                         "Failed to replace miles32.dll: {e}\n\n \
@@ -131,30 +193,15 @@ extern "C" fn StartLauncher(
                     .as_str(),
                     "CoD4x Launcher",
                 );
+                // The instance waiting for this one must not restart the game
+                if silent && elevated {
+                    std::process::exit(1);
+                }
                 return;
-            } else {
-                message_box(
-                    "Successfully fixed miles32.dll.\nYou can restart the game now.",
-                    "CoD4x Launcher",
-                );
             }
-            std::process::exit(0);
+            finish_fix("Successfully fixed miles32.dll.", silent, elevated);
         }
     };
 
     run_thread(hinstance);
 }
-
-fn get_cmdline_value<'a>(value_name: &str, cmdline_args: &'a [String]) -> Option<&'a String> {
-    let mut args = cmdline_args.iter();
-    while let Some(arg) = args.next() {
-        if arg == "+set" || arg == "+seta" {
-            if let Some(arg) = args.next() {
-                if arg == value_name {
-                    return args.next();
-                }
-            }
-        }
-    }
-    None
-}
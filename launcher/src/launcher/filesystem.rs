@@ -67,16 +67,12 @@ pub fn get_appdata_cod4_path() -> anyhow::Result<std::path::PathBuf> {
     Ok(PathBuf::from(app_data).join("CallofDuty4MW"))
 }
 
-pub fn appdata_bin_path() -> anyhow::Result<std::path::PathBuf> {
-    Ok(get_appdata_cod4_path()?.join("bin"))
-}
-
-pub fn appdata_main_path() -> anyhow::Result<std::path::PathBuf> {
-    Ok(get_appdata_cod4_path()?.join("main"))
+pub fn appdata_cache_path() -> anyhow::Result<std::path::PathBuf> {
+    Ok(get_appdata_cod4_path()?.join("cache"))
 }
 
-pub fn appdata_zone_path() -> anyhow::Result<std::path::PathBuf> {
-    Ok(get_appdata_cod4_path()?.join("zone"))
+pub fn appdata_bin_path() -> anyhow::Result<std::path::PathBuf> {
+    Ok(get_appdata_cod4_path()?.join("bin"))
 }
 
 pub fn set_current_directory(path: &std::path::Path) {
@@ -1,11 +1,15 @@
+use super::cmdline;
 use super::filesystem as fs;
+use super::hash;
 use super::http;
+use super::log;
 use super::module;
+use super::msg_box;
 use super::process;
 use super::security_info;
-use super::sha1;
 use super::zip;
-use crate::launcher::updater::github;
+use crate::launcher::updater::release;
+use crate::launcher::updater::source::{self, Project};
 use std::error::Error;
 use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
 use std::io::{Read, Seek, SeekFrom, Write};
@@ -40,16 +44,19 @@ pub fn is_large_address_aware() -> bool {
     is_large_address_aware_impl().unwrap_or(true)
 }
 
-pub fn replace_module() -> anyhow::Result<()> {
+/// Silent updates only log that elevation is needed and wait for the elevated instance.
+pub fn replace_module(silent: bool) -> anyhow::Result<()> {
     let module_path = module::get_path();
     let install_dir = module_path.parent().ok_or(ReplaceIw3mpError::InvalidPath)?;
 
     if !fs::is_writable(install_dir) {
-        crate::launcher::msg_box::message_box(
-            "CoD4x launcher needs to update file iw3mp.exe and will require elevated permissions",
-            "Call of Duty 4 - Launcher",
-        );
-        process::restart(process::Privileges::Admin, Some("+set elevated 1"))?;
+        let message =
+            "CoD4x launcher needs to update file iw3mp.exe and will require elevated permissions";
+        match silent {
+            true => log::write(message),
+            false => msg_box::message_box(message, "Call of Duty 4 - Launcher"),
+        }
+        process::elevate(cmdline::get_elevated_params().as_str(), silent)?;
     }
 
     let savepath = fs::appdata_bin_path()?;
@@ -59,13 +66,15 @@ pub fn replace_module() -> anyhow::Result<()> {
     let new_iw3mp_path = install_dir.join("iw3mp.new");
     std::fs::create_dir_all(&savepath)?;
 
-    const IW3MP_HASH: &str = "3323b3882f9465a4c66cf298833435150effb153";
+    // Only the SHA-1 of the original file is known so far. A `sha256:` pin added here takes
+    // precedence, as files are verified against the strongest pinned hash
+    const IW3MP_HASHES: &[&str] = &["sha1:3323b3882f9465a4c66cf298833435150effb153"];
+    let expected_hashes = hash::parse_list(IW3MP_HASHES);
 
-    if sha1::digest(save_iw3mp_path.as_path()).map_or(true, |module_hash| module_hash != IW3MP_HASH)
-    {
-        let release_info = github::fetch_release_information("callofduty4x/CoD4x_Client_pub")?;
+    if !hash::verify(save_iw3mp_path.as_path(), &expected_hashes) {
+        let release_info = source::fetch_release_information(Project::CoD4x)?;
         let core_asset =
-            github::find_asset(&release_info, "^core$").ok_or(ReplaceIw3mpError::AssetNotFound)?;
+            release::find_asset(&release_info, "^core$").ok_or(ReplaceIw3mpError::AssetNotFound)?;
 
         http::download_file(
             core_asset.url.as_str(),
@@ -79,9 +88,7 @@ pub fn replace_module() -> anyhow::Result<()> {
             save_iw3mp_path.as_path(),
         )?;
 
-        if sha1::digest(save_iw3mp_path.as_path())
-            .map_or(true, |module_hash| module_hash != IW3MP_HASH)
-        {
+        if !hash::verify(save_iw3mp_path.as_path(), &expected_hashes) {
             return Err(ReplaceIw3mpError::IntegrityFailure.into());
         } else {
             std::fs::remove_file(zip_iw3mp_path.as_path()).ok();
@@ -1,10 +1,14 @@
+use super::cmdline;
 use super::filesystem as fs;
+use super::hash;
 use super::http;
+use super::log;
 use super::module;
+use super::msg_box;
 use super::process;
-use super::sha1;
 use super::zip;
-use crate::launcher::updater::github;
+use crate::launcher::updater::release;
+use crate::launcher::updater::source::{self, Project};
 use core::ffi::{c_char, c_void, CStr};
 use libloading::Library;
 use std::error::Error;
@@ -48,18 +52,38 @@ pub fn load_module(
     }
 }
 
-pub fn replace_module() -> anyhow::Result<()> {
+// Only the SHA-1 of the original file is known so far. A `sha256:` pin added here takes
+// precedence, as files are verified against the strongest pinned hash
+const MILES32_HASHES: &[&str] = &["sha1:055dc05a4c175b84dffb87b2380714128e5b27dd"];
+
+/// Whether the miles32.dll next to the game is the original one.
+pub fn is_pure() -> bool {
+    let module_path = module::get_path();
+    let Some(install_dir) = module_path.parent() else {
+        return false;
+    };
+
+    hash::verify(
+        install_dir.join("miles32.dll").as_path(),
+        &hash::parse_list(MILES32_HASHES),
+    )
+}
+
+/// Silent updates only log that elevation is needed and wait for the elevated instance.
+pub fn replace_module(silent: bool) -> anyhow::Result<()> {
     let module_path = module::get_path();
     let install_dir = module_path
         .parent()
         .ok_or(ReplaceMiles32Error::InvalidPath)?;
 
     if !fs::is_writable(install_dir) {
-        crate::launcher::msg_box::message_box(
-            "CoD4x launcher needs to update file miles32.dll and will require elevated permissions",
-            "Call of Duty 4 - Launcher",
-        );
-        process::restart(process::Privileges::Admin, Some("+set elevated 1"))?;
+        let message =
+            "CoD4x launcher needs to update file miles32.dll and will require elevated permissions";
+        match silent {
+            true => log::write(message),
+            false => msg_box::message_box(message, "Call of Duty 4 - Launcher"),
+        }
+        process::elevate(cmdline::get_elevated_params().as_str(), silent)?;
     }
 
     let savepath = fs::appdata_bin_path()?;
@@ -68,14 +92,12 @@ pub fn replace_module() -> anyhow::Result<()> {
     let new_miles32_path = install_dir.join("miles32.dll");
     std::fs::create_dir_all(&savepath)?;
 
-    const MILES32_HASH: &str = "055dc05a4c175b84dffb87b2380714128e5b27dd";
+    let expected_hashes = hash::parse_list(MILES32_HASHES);
 
-    if sha1::digest(org_miles32_path.as_path())
-        .map_or(true, |module_hash| module_hash != MILES32_HASH)
-    {
-        let release_info = github::fetch_release_information("callofduty4x/CoD4x_Client_pub")?;
-        let mss_asset =
-            github::find_asset(&release_info, "^mss$").ok_or(ReplaceMiles32Error::AssetNotFound)?;
+    if !hash::verify(org_miles32_path.as_path(), &expected_hashes) {
+        let release_info = source::fetch_release_information(Project::CoD4x)?;
+        let mss_asset = release::find_asset(&release_info, "^mss$")
+            .ok_or(ReplaceMiles32Error::AssetNotFound)?;
 
         http::download_file(
             mss_asset.url.as_str(),
@@ -89,9 +111,7 @@ pub fn replace_module() -> anyhow::Result<()> {
             org_miles32_path.as_path(),
         )?;
 
-        if sha1::digest(org_miles32_path.as_path())
-            .map_or(true, |module_hash| module_hash != MILES32_HASH)
-        {
+        if !hash::verify(org_miles32_path.as_path(), &expected_hashes) {
             return Err(ReplaceMiles32Error::IntegrityFailure.into());
         } else {
             std::fs::remove_file(zip_miles32_path.as_path()).ok();
@@ -127,6 +147,7 @@ fn convert_bytes_to_string(bytes: &[u8]) -> Result<String, std::str::Utf8Error>
 pub enum Miles32LoadError {
     ModuleNotFound,
     MissingProcedure(String),
+    Impure,
 }
 
 impl Miles32LoadError {
@@ -134,6 +155,7 @@ impl Miles32LoadError {
         match self {
             Self::ModuleNotFound => "Miles32 DLL not found".to_string(),
             Self::MissingProcedure(name) => format!("Missing Miles32 procedure '{name}'"),
+            Self::Impure => "Miles32 DLL doesn't match the original".to_string(),
         }
     }
 }
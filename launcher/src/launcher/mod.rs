@@ -1,15 +1,17 @@
+mod cmdline;
 mod cod4x;
+mod config;
 mod entrypoint;
 mod error;
 mod filesystem;
-mod http;
 mod iw3mp;
 mod miles32;
 mod module;
 mod msg_box;
 mod process;
 mod security_info;
-mod sha1;
 mod updater;
 mod wstring;
 mod zip;
+
+use cod4x_updater::{hash, http, log};
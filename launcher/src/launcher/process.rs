@@ -3,10 +3,15 @@ use super::{error, module, wstring};
 use std::error::Error;
 use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
 use user32::AllowSetForegroundWindow;
-use winapi::um::processthreadsapi::GetProcessId;
+use winapi::shared::minwindef::DWORD;
+use winapi::um::handleapi::CloseHandle;
+use winapi::um::processthreadsapi::{GetExitCodeProcess, GetProcessId};
 use winapi::um::shellapi::{
     ShellExecuteExW, LPSHELLEXECUTEINFOW, SEE_MASK_NOCLOSEPROCESS, SHELLEXECUTEINFOW,
 };
+use winapi::um::synchapi::WaitForSingleObject;
+use winapi::um::winbase::INFINITE;
+use winapi::um::winnt::HANDLE;
 use winapi::um::winuser::SW_SHOWNORMAL;
 
 pub enum Privileges {
@@ -16,6 +21,41 @@ pub enum Privileges {
 }
 
 pub fn restart(privileges: Privileges, params: Option<&str>) -> anyhow::Result<()> {
+    let process = start(privileges, params)?;
+    if !process.is_null() {
+        unsafe { AllowSetForegroundWindow(GetProcessId(process)) };
+    }
+
+    std::process::exit(0);
+}
+
+/// Runs `params` in an elevated instance of the game. With `wait`, this instance waits for it
+/// and then restarts without administrator rights, otherwise the elevated instance takes over.
+pub fn elevate(params: &str, wait: bool) -> anyhow::Result<()> {
+    if !wait {
+        return restart(Privileges::Admin, Some(params));
+    }
+
+    let process = start(Privileges::Admin, Some(params))?;
+    if process.is_null() {
+        return Err(ElevatedProcessError::NoProcess.into());
+    }
+
+    let mut exit_code: DWORD = 0;
+    let exit_code_found = unsafe {
+        WaitForSingleObject(process, INFINITE);
+        let exit_code_found = GetExitCodeProcess(process, &mut exit_code) != 0;
+        CloseHandle(process);
+        exit_code_found
+    };
+    if !exit_code_found || exit_code != 0 {
+        return Err(ElevatedProcessError::Failed.into());
+    }
+
+    restart(Privileges::User, None)
+}
+
+fn start(privileges: Privileges, params: Option<&str>) -> anyhow::Result<HANDLE> {
     let method = match privileges {
         Privileges::User => "open",
         Privileges::Admin => "runas",
@@ -40,11 +80,7 @@ pub fn restart(privileges: Privileges, params: Option<&str>) -> anyhow::Result<(
         return Err(RestartProcessError::new(error::get_error_string()).into());
     }
 
-    if !sei.hProcess.is_null() {
-        unsafe { AllowSetForegroundWindow(GetProcessId(sei.hProcess)) };
-    }
-
-    std::process::exit(0);
+    Ok(sei.hProcess)
 }
 
 struct RestartProcessError {
@@ -72,3 +108,31 @@ impl Debug for RestartProcessError {
 }
 
 impl Error for RestartProcessError {}
+
+enum ElevatedProcessError {
+    NoProcess,
+    Failed,
+}
+
+impl ElevatedProcessError {
+    fn message(&self) -> &str {
+        match self {
+            Self::NoProcess => "Failed to wait for the elevated process",
+            Self::Failed => "The elevated process failed",
+        }
+    }
+}
+
+impl Display for ElevatedProcessError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl Debug for ElevatedProcessError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl Error for ElevatedProcessError {}
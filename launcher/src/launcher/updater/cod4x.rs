@@ -1,194 +1,292 @@
-use super::component::{Component, Update};
+use super::component::{self, Component, Update};
+use super::Mode;
 use crate::launcher::cod4x as cod4x_module;
 use crate::launcher::filesystem as fs;
-use crate::launcher::http;
-use crate::launcher::sha1;
-use crate::launcher::updater::github;
-use semver::Version;
+use crate::launcher::hash::{self, Hash};
+use crate::launcher::updater::release;
+use crate::launcher::updater::source::{self, Project};
+use crate::launcher::{http, log};
+use cod4x_updater::delta;
+use cod4x_updater::install;
+use cod4x_updater::manifest::{self, Artifact, ArtifactType};
+use regex::Regex;
 use std::collections::HashMap;
 use std::error::Error;
 use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 
 pub struct CoD4xComponent {
-    release_information: Arc<github::ReleaseInformation>,
+    release_information: Arc<release::ReleaseInformation>,
+    hashes: String,
+    artifacts: Vec<Artifact>,
 }
 
 impl CoD4xComponent {
-    pub fn new() -> anyhow::Result<Self> {
+    /// The release hashes are needed up front to tell whether the installed mod assets are
+    /// up to date, and to verify the artifact manifest of the release. Repairs verify the
+    /// installed files against the release of the installed CoD4x DLL.
+    pub fn new(mode: Mode) -> anyhow::Result<Self> {
+        let release_information = match mode {
+            Mode::Update => source::fetch_release_information(Project::CoD4x)?,
+            Mode::Repair => source::fetch_installed_release_information(
+                Project::CoD4x,
+                cod4x_module::get_module_version().ok().as_ref(),
+            )?,
+        };
+        let hashes =
+            release::fetch_hashes(&release_information, &Project::CoD4x.signature_policy())?;
+        let artifacts = manifest::fetch(
+            &release_information,
+            &release::parse_hashes(hashes.as_str()),
+        )?;
+
         Ok(Self {
-            release_information: Arc::new(github::fetch_release_information(
-                "callofduty4x/CoD4x_Client_pub",
-            )?),
+            release_information: Arc::new(release_information),
+            hashes,
+            artifacts,
         })
     }
 
-    fn get_module_update(
+    fn create_update(
         &self,
-        pattern: &str,
-        display_name: &str,
-    ) -> anyhow::Result<Option<Update>> {
-        let mut upstream_tag = self.release_information.as_ref().tag_name.clone();
-        if upstream_tag.matches('.').count() < 2 {
-            upstream_tag.push_str(".0");
-        }
+        artifact: &Artifact,
+        asset: &release::AssetInformation,
+        current: Option<semver::Version>,
+    ) -> anyhow::Result<Update> {
+        Ok(Update {
+            display_name: artifact.display_name.clone(),
+            artifact_name: artifact.pattern.clone(),
+            current: current.clone(),
+            upstream: release::parse_version(self.release_information.as_ref().tag_name.as_str())?,
+            download_size: asset.size,
+            requires_elevate: artifact.requires_elevate,
+            requires_restart: artifact.requires_restart,
+            // The game can't start without a working CoD4x DLL
+            mandatory: artifact.mandatory
+                || self.release_information.security
+                || (artifact.artifact_type == ArtifactType::Module && current.is_none()),
+        })
+    }
 
-        let upstream = Version::parse(upstream_tag.as_str())?;
-        let current = cod4x_module::get_module_version().ok();
-
-        let needs_update = current.as_ref().is_none_or(|c| upstream > *c);
-        if needs_update {
-            Ok(Some(Update {
-                display_name: display_name.to_string(),
-                artifact_name: pattern.to_string(),
-                current,
-                upstream,
-                requires_elevate: false,
-                requires_restart: false,
-            }))
-        } else {
-            Ok(None)
-        }
+    fn get_artifact_path(
+        &self,
+        artifact: &Artifact,
+        asset: &release::AssetInformation,
+    ) -> anyhow::Result<std::path::PathBuf> {
+        let artifact_path = artifact
+            .path(fs::get_appdata_cod4_path()?.as_path(), asset.name.as_str())
+            .ok_or(CoD4xAssetError::NameError)?;
+        Ok(artifact_path)
     }
 
-    fn get_cod4x_mod_update(
+    fn get_artifact_update(
         &self,
-        base_path: std::path::PathBuf,
-        file_name: &str,
-        display_name: &str,
+        artifact: &Artifact,
+        hashes: &HashMap<&str, Vec<Hash>>,
     ) -> anyhow::Result<Option<Update>> {
-        let artifact_path = base_path.join(file_name);
-        if !artifact_path.exists() {
-            Ok(Some(Update {
-                display_name: display_name.to_string(),
-                artifact_name: file_name.to_string(),
-                current: None,
-                upstream: Version::new(1, 0, 0),
-                requires_elevate: false,
-                requires_restart: false,
-            }))
-        } else {
-            Ok(None)
+        let Some(asset) = release::find_asset(&self.release_information, &artifact.pattern) else {
+            return Ok(None);
+        };
+
+        match artifact.artifact_type {
+            ArtifactType::Module => {
+                let upstream =
+                    release::parse_version(self.release_information.as_ref().tag_name.as_str())?;
+                let current = cod4x_module::get_module_version().ok();
+
+                let needs_update = current.as_ref().is_none_or(|c| upstream > *c);
+                match needs_update {
+                    true => Ok(Some(self.create_update(artifact, asset, current)?)),
+                    false => Ok(None),
+                }
+            }
+            ArtifactType::File => self.get_file_update(artifact, asset, hashes),
         }
     }
 
-    fn update_artifact(
+    fn get_artifact_repair(
         &self,
-        artifact: &Update,
-        hashes: &HashMap<&str, &str>,
-        progress_callback: &http::ProgressCallback,
-    ) -> anyhow::Result<()> {
-        let asset =
-            match github::find_asset(&self.release_information, artifact.artifact_name.as_str()) {
-                Some(asset) => asset,
-                None => return Err(CoD4xAssetError::NotFound.into()),
-            };
+        artifact: &Artifact,
+        hashes: &HashMap<&str, Vec<Hash>>,
+    ) -> anyhow::Result<Option<Update>> {
+        let Some(asset) = release::find_asset(&self.release_information, &artifact.pattern) else {
+            return Ok(None);
+        };
 
-        match artifact.artifact_name.as_str() {
-            "^cod4x_([0-9]+).dll" => self.update_cod4x(asset, hashes, progress_callback)?,
-            "jcod4x_00.iwd" => {
-                self.update_mod_asset(fs::appdata_main_path()?, asset, hashes, progress_callback)?
-            }
-            "cod4x_ambfix.ff" => {
-                self.update_mod_asset(fs::appdata_zone_path()?, asset, hashes, progress_callback)?
-            }
-            "cod4x_patch.ff" => {
-                self.update_mod_asset(fs::appdata_zone_path()?, asset, hashes, progress_callback)?
-            }
-            "cod4x_patchv2.ff" => {
-                self.update_mod_asset(fs::appdata_zone_path()?, asset, hashes, progress_callback)?
+        match artifact.artifact_type {
+            ArtifactType::Module => {
+                let expected_hashes = artifact
+                    .expected_hashes(asset.name.as_str(), hashes)
+                    .ok_or(CoD4xAssetError::HashNotFound)?;
+
+                let artifact_path = self.get_artifact_path(artifact, asset)?;
+                match hash::verify(artifact_path.as_path(), &expected_hashes) {
+                    true => Ok(None),
+                    false => Ok(Some(self.create_update(artifact, asset, None)?)),
+                }
             }
-            _ => {}
-        };
+            ArtifactType::File => self.get_file_update(artifact, asset, hashes),
+        }
+    }
 
-        Ok(())
+    fn get_file_update(
+        &self,
+        artifact: &Artifact,
+        asset: &release::AssetInformation,
+        hashes: &HashMap<&str, Vec<Hash>>,
+    ) -> anyhow::Result<Option<Update>> {
+        let artifact_path = self.get_artifact_path(artifact, asset)?;
+        let expected_hashes = artifact.expected_hashes(asset.name.as_str(), hashes);
+
+        match install::is_installed(artifact_path.as_path(), expected_hashes.as_deref()) {
+            true => Ok(None),
+            false => Ok(Some(self.create_update(artifact, asset, None)?)),
+        }
     }
 
-    fn update_cod4x(
+    fn update_artifact(
         &self,
-        asset: &github::AssetInformation,
-        hashes: &HashMap<&str, &str>,
+        update: &Update,
+        hashes: &HashMap<&str, Vec<Hash>>,
         progress_callback: &http::ProgressCallback,
     ) -> anyhow::Result<()> {
-        let expected_hash = hashes
-            .get(&asset.name.as_str())
+        let artifact = manifest::find_artifact(&self.artifacts, update.artifact_name.as_str())
+            .ok_or(CoD4xAssetError::NotFound)?;
+        let asset = release::find_asset(&self.release_information, &artifact.pattern)
+            .ok_or(CoD4xAssetError::NotFound)?;
+        let expected_hashes = artifact
+            .expected_hashes(asset.name.as_str(), hashes)
             .ok_or(CoD4xAssetError::HashNotFound)?;
+        let artifact_path = self.get_artifact_path(artifact, asset)?;
 
-        let savepath = fs::appdata_bin_path()?;
-        let version_dir = std::path::Path::new(&asset.name)
-            .file_stem()
-            .ok_or(CoD4xAssetError::NameError)?;
-        let destination_dir = savepath.join(version_dir);
-        std::fs::create_dir_all(&destination_dir)?;
-
-        let cod4x_path = destination_dir.join(&asset.name);
-        let download_path = cod4x_path.with_extension("part");
-        http::download_file(
-            asset.url.as_str(),
-            download_path.as_path(),
-            progress_callback,
-        )?;
-
-        if sha1::digest(download_path.as_path())
-            .map_or(true, |asset_hash| asset_hash != *expected_hash)
-        {
-            return Err(CoD4xAssetError::IntegrityFailure.into());
+        // Prefer patching an installed module, fall back to the full download on any failure
+        if artifact.artifact_type == ArtifactType::Module {
+            if let Some(destination_dir) = artifact_path.parent() {
+                std::fs::create_dir_all(destination_dir)?;
+            }
+            let patched_path = artifact_path.with_extension("patched");
+            let patched = self
+                .patch_cod4x(asset, hashes, patched_path.as_path(), progress_callback)
+                .and_then(
+                    |_| match hash::verify(patched_path.as_path(), &expected_hashes) {
+                        true => Ok(()),
+                        false => Err(CoD4xAssetError::DeltaMismatch.into()),
+                    },
+                );
+            match patched {
+                Ok(_) => {
+                    std::fs::rename(patched_path, artifact_path)?;
+                    return Ok(());
+                }
+                Err(e) => log::write(
+                    format!("Delta update of {} failed, downloading it: {e}", asset.name).as_str(),
+                ),
+            }
+            std::fs::remove_file(patched_path.as_path()).ok();
         }
 
-        std::fs::rename(download_path, cod4x_path)?;
-        Ok(())
+        install::download_verified(
+            asset,
+            artifact_path.as_path(),
+            &expected_hashes,
+            progress_callback,
+        )
     }
 
-    fn update_mod_asset(
+    /// Builds the DLL of `asset` at `destination` from the newest installed version for which the
+    /// release provides a delta asset named `cod4x_<installed>_<upstream>.bsdiff`.
+    fn patch_cod4x(
         &self,
-        base_path: std::path::PathBuf,
-        asset: &github::AssetInformation,
-        hashes: &HashMap<&str, &str>,
+        asset: &release::AssetInformation,
+        hashes: &HashMap<&str, Vec<Hash>>,
+        destination: &std::path::Path,
         progress_callback: &http::ProgressCallback,
     ) -> anyhow::Result<()> {
-        let expected_hash = hashes
-            .get(&asset.name.as_str())
-            .ok_or(CoD4xAssetError::HashNotFound)?;
+        let upstream = get_module_number(asset.name.as_str()).ok_or(CoD4xAssetError::NameError)?;
 
-        std::fs::create_dir_all(&base_path)?;
+        let mut installed_modules = cod4x_module::get_installed_modules()?
+            .into_iter()
+            .filter_map(|path| {
+                let number = get_module_number(path.file_name()?.to_str()?)?.to_string();
+                (number != upstream).then_some((number, path))
+            })
+            .collect::<Vec<_>>();
+        installed_modules.sort_by_key(|(number, _)| std::cmp::Reverse(number.parse::<u32>().ok()));
 
-        let file_path = base_path.join(&asset.name);
-        let download_path = file_path.with_extension("part");
-        http::download_file(
-            asset.url.as_str(),
-            download_path.as_path(),
-            progress_callback,
-        )?;
+        for (installed, base_path) in installed_modules {
+            let pattern = format!(r"^cod4x_{installed}_{upstream}\.bsdiff$");
+            let Some(patch_asset) = release::find_asset(&self.release_information, &pattern) else {
+                continue;
+            };
+            let expected_hash = hashes
+                .get(&patch_asset.name.as_str())
+                .and_then(|expected_hashes| hash::strongest(expected_hashes))
+                .ok_or(CoD4xAssetError::HashNotFound)?;
 
-        if sha1::digest(download_path.as_path())
-            .map_or(true, |asset_hash| asset_hash != *expected_hash)
-        {
-            return Err(CoD4xAssetError::IntegrityFailure.into());
+            let patch_path = destination.with_file_name(&patch_asset.name);
+            let actual_hash = http::download_file_hashed(
+                patch_asset.url.as_str(),
+                patch_path.as_path(),
+                expected_hash.algorithm,
+                progress_callback,
+            )?;
+
+            let result = match hash::check(patch_asset.name.as_str(), expected_hash, &actual_hash) {
+                Ok(_) => delta::apply(base_path.as_path(), patch_path.as_path(), destination)
+                    .map_err(|e| e.into()),
+                Err(e) => Err(e.into()),
+            };
+            http::discard_download(patch_path.as_path());
+            return result;
         }
 
-        std::fs::rename(download_path, file_path)?;
-        Ok(())
+        Err(CoD4xAssetError::DeltaNotFound.into())
     }
 }
 
+/// Version number of a CoD4x DLL from its file name, e.g. `21` for `cod4x_21.dll`.
+fn get_module_number(file_name: &str) -> Option<&str> {
+    static MODULE_REGEX: OnceLock<Regex> = OnceLock::new();
+    let regex = MODULE_REGEX
+        .get_or_init(|| Regex::new(r"^cod4x_([0-9]+)\.dll$").expect("Failed to compile regex"));
+    Some(regex.captures(file_name)?.get(1)?.as_str())
+}
+
 impl Component for CoD4xComponent {
     fn name(&self) -> &str {
         "CoD4x game"
     }
 
+    fn project(&self) -> Project {
+        Project::CoD4x
+    }
+
+    fn release_notes(&self) -> Option<String> {
+        self.release_information.notes()
+    }
+
     fn get_updates(&self) -> anyhow::Result<Vec<Update>> {
-        let updates = [
-            self.get_module_update("^cod4x_([0-9]+).dll", "CoD4x DLL")?,
-            self.get_cod4x_mod_update(fs::appdata_main_path()?, "jcod4x_00.iwd", "jcod4x")?,
-            self.get_cod4x_mod_update(fs::appdata_zone_path()?, "cod4x_ambfix.ff", "ambfix")?,
-            self.get_cod4x_mod_update(fs::appdata_zone_path()?, "cod4x_patch.ff", "patch")?,
-            self.get_cod4x_mod_update(fs::appdata_zone_path()?, "cod4x_patchv2.ff", "patch v2")?,
-        ]
-        .into_iter()
-        .flatten()
-        .collect();
+        let hashes = release::parse_hashes(self.hashes.as_str());
+
+        let updates = self
+            .artifacts
+            .iter()
+            .map(|artifact| self.get_artifact_update(artifact, &hashes))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(updates.into_iter().flatten().collect())
+    }
+
+    fn get_repairs(&self) -> anyhow::Result<Vec<Update>> {
+        let hashes = release::parse_hashes(self.hashes.as_str());
+
+        let repairs = self
+            .artifacts
+            .iter()
+            .map(|artifact| self.get_artifact_repair(artifact, &hashes))
+            .collect::<anyhow::Result<Vec<_>>>()?;
 
-        Ok(updates)
+        Ok(repairs.into_iter().flatten().collect())
     }
 
     fn update(
@@ -197,8 +295,8 @@ impl Component for CoD4xComponent {
         status_update: &dyn Fn(String),
         progress_callback: &http::ProgressCallback,
     ) -> anyhow::Result<()> {
-        let hashes_str = github::fetch_hashes(&self.release_information)?;
-        let hashes = github::parse_hashes(hashes_str.as_str());
+        component::report_release(&self.release_information, status_update);
+        let hashes = release::parse_hashes(self.hashes.as_str());
 
         for update_artifact in updates {
             status_update(format!("Downloading {}...", update_artifact.display_name));
@@ -213,7 +311,8 @@ pub enum CoD4xAssetError {
     NotFound,
     NameError,
     HashNotFound,
-    IntegrityFailure,
+    DeltaNotFound,
+    DeltaMismatch,
 }
 
 impl CoD4xAssetError {
@@ -222,7 +321,8 @@ impl CoD4xAssetError {
             Self::NotFound => "Couldn't find CoD4x asset",
             Self::NameError => "Unexpected CoD4x asset name",
             Self::HashNotFound => "Couldn't find CoD4x asset hash",
-            Self::IntegrityFailure => "CoD4x asset integrity verification failed",
+            Self::DeltaNotFound => "Couldn't find a CoD4x delta asset for the installed version",
+            Self::DeltaMismatch => "The patched CoD4x DLL doesn't match the release hash",
         }
     }
 }
@@ -1,4 +1,6 @@
+use super::source::Project;
 use crate::launcher::http;
+use crate::launcher::updater::release::ReleaseInformation;
 use semver::Version;
 
 pub struct Update {
@@ -6,15 +8,28 @@ pub struct Update {
     pub artifact_name: String,
     pub current: Option<Version>,
     pub upstream: Version,
+    /// Size of the artifact download, if the release source provides it.
+    pub download_size: Option<u64>,
     pub requires_elevate: bool,
     pub requires_restart: bool,
+    /// Mandatory updates can't be deselected when choosing what to install.
+    pub mandatory: bool,
 }
 
 pub trait Component: Send + Sync {
     fn name(&self) -> &str;
 
+    fn project(&self) -> Project;
+
+    /// Notes of the release the component updates to, as plain text.
+    fn release_notes(&self) -> Option<String>;
+
     fn get_updates(&self) -> anyhow::Result<Vec<Update>>;
 
+    /// Installed artifacts that don't match the hashes of the release.
+    fn get_repairs(&self) -> anyhow::Result<Vec<Update>>;
+
+    /// Installs `updates`, which may be any subset of the updates the component returned.
     fn update(
         &self,
         updates: &[Update],
@@ -24,3 +39,11 @@ pub trait Component: Send + Sync {
 }
 
 pub type ComponentUpdates = (Vec<Update>, Box<dyn Component>);
+
+/// Reports the release and the source a component installs from.
+pub fn report_release(release_information: &ReleaseInformation, status_report: &dyn Fn(String)) {
+    status_report(format!(
+        "Using release {} from {}",
+        release_information.tag_name, release_information.source
+    ));
+}
@@ -9,7 +9,8 @@ use std::ops::Deref;
 use std::rc::Rc;
 use std::sync::Arc;
 
-pub fn run_gui(updates: Arc<Vec<ComponentUpdates>>) -> anyhow::Result<()> {
+/// Prepares the UI of the updater windows. Calling it again has no effect.
+pub fn init() -> anyhow::Result<()> {
     nwg::init()?;
     //nwg::Font::set_global_family("Segoe UI")?;
     let mut font = nwg::Font::default();
@@ -19,6 +20,12 @@ pub fn run_gui(updates: Arc<Vec<ComponentUpdates>>) -> anyhow::Result<()> {
         .build(&mut font)?;
     nwg::Font::set_global_default(Some(font));
     enable_visual_styles();
+
+    Ok(())
+}
+
+pub fn run_gui(updates: Arc<Vec<ComponentUpdates>>) -> anyhow::Result<()> {
+    init()?;
     let _updater = Updater::build_ui(Updater::new(updates))?;
     nwg::dispatch_thread_events();
 
@@ -109,6 +116,11 @@ impl nwg::NativeUi<UpdaterGui> for Updater {
             .parent(&data.window)
             .build(&mut data.progress_bar)?;
 
+        nwg::Label::builder()
+            .text("")
+            .parent(&data.window)
+            .build(&mut data.progress_label)?;
+
         nwg::Notice::builder()
             .parent(&data.window)
             .build(&mut data.progress_notice)?;
@@ -131,27 +143,21 @@ impl nwg::NativeUi<UpdaterGui> for Updater {
         let handle_events = move |evt, _evt_data, handle: nwg::ControlHandle| {
             if let Some(ui) = evt_ui.upgrade() {
                 match evt {
-                    E::OnInit => {
-                        if handle == ui.window {
-                            Updater::start_download(&ui);
-                        }
+                    E::OnInit if handle == ui.window => {
+                        Updater::start_download(&ui);
                     }
-                    E::OnButtonClick => {
-                        if handle == ui.button {
-                            if ui.button.text() == "Cancel" {
-                                Updater::cancel_update(&ui);
-                                ui.button.set_text("OK");
-                            } else if ui.button.text() == "OK" {
-                                ui.window.close();
-                            }
-                        }
-                    }
-                    E::OnWindowClose => {
-                        if handle == ui.window {
+                    E::OnButtonClick if handle == ui.button => {
+                        if ui.button.text() == "Cancel" {
                             Updater::cancel_update(&ui);
-                            nwg::stop_thread_dispatch();
+                            ui.button.set_text("OK");
+                        } else if ui.button.text() == "OK" {
+                            ui.window.close();
                         }
                     }
+                    E::OnWindowClose if handle == ui.window => {
+                        Updater::cancel_update(&ui);
+                        nwg::stop_thread_dispatch();
+                    }
                     E::OnNotice => {
                         if handle == ui.progress_notice {
                             Updater::on_progress_notice(&ui);
@@ -195,6 +201,12 @@ impl nwg::NativeUi<UpdaterGui> for Updater {
                 width: D::Auto,
                 height: D::Points(25.0),
             })
+            // Progress details
+            .child(&ui.progress_label)
+            .child_size(Size {
+                width: D::Auto,
+                height: D::Points(20.0),
+            })
             // Button
             .child(&ui.button)
             .child_align_self(AlignSelf::FlexEnd)
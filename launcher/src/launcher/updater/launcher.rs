@@ -1,28 +1,53 @@
-use super::component::{Component, Update};
+use super::component::{self, Component, Update};
+use super::state::UpdateState;
+use super::Mode;
 use crate::launcher::filesystem as fs;
-use crate::launcher::http;
-use crate::launcher::sha1;
-use crate::launcher::updater::github;
+use crate::launcher::hash::{self, Hash};
+use crate::launcher::updater::release;
+use crate::launcher::updater::source::{self, Project};
+use crate::launcher::{cmdline, http, log, module, process};
+use libloading::Library;
 use semver::Version;
 use std::collections::HashMap;
 use std::error::Error;
 use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::path::Path;
+use std::process::Command;
 use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
 
 const LAUNCHER_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// Written next to a freshly installed launcher.dll, holding its version, until it first starts.
+const PENDING_MARKER: &str = "launcher.pending";
+/// Present while a freshly installed launcher.dll starts for the first time.
+const BOOTING_MARKER: &str = "launcher.booting";
+/// Written by a launcher.dll started for its self-test, holding its version.
+const SELF_TEST_MARKER: &str = "launcher.selftest";
+
+/// Cvar starting the game only to test that the loader can start launcher.dll.
+const SELF_TEST_CVAR: &str = "launcher_selftest";
+/// Time the self-test of a downloaded launcher.dll may take before it counts as failed.
+const SELF_TEST_TIMEOUT: Duration = Duration::from_secs(30);
+
 pub struct LauncherComponent {
-    release_information: Arc<github::ReleaseInformation>,
+    release_information: Arc<release::ReleaseInformation>,
 }
 
 impl LauncherComponent {
-    pub fn new() -> anyhow::Result<Self> {
+    /// Repairs verify the running launcher against the release it comes from.
+    pub fn new(mode: Mode) -> anyhow::Result<Self> {
+        let release_information = match mode {
+            Mode::Update => source::fetch_release_information(Project::Launcher)?,
+            Mode::Repair => source::fetch_installed_release_information(
+                Project::Launcher,
+                Version::parse(LAUNCHER_VERSION).ok().as_ref(),
+            )?,
+        };
+
         Ok(Self {
-            // TODO: add a backup update mechanism for github makes some changes that make the
-            // following fail:
-            release_information: Arc::new(github::fetch_release_information(
-                "callofduty4x/CoD4x-launcher",
-            )?),
+            release_information: Arc::new(release_information),
         })
     }
 
@@ -31,7 +56,8 @@ impl LauncherComponent {
         pattern: &str,
         display_name: &str,
     ) -> anyhow::Result<Option<Update>> {
-        let upstream_version = Version::parse(self.release_information.as_ref().tag_name.as_str())?;
+        let upstream_version =
+            release::parse_version(self.release_information.as_ref().tag_name.as_str())?;
         let current_version = Version::parse(LAUNCHER_VERSION)?;
 
         if upstream_version > current_version {
@@ -40,8 +66,11 @@ impl LauncherComponent {
                 artifact_name: pattern.to_string(),
                 current: Some(current_version),
                 upstream: upstream_version,
+                download_size: release::find_asset(&self.release_information, pattern)
+                    .and_then(|asset| asset.size),
                 requires_elevate: false,
                 requires_restart: true,
+                mandatory: self.release_information.security,
             }))
         } else {
             Ok(None)
@@ -51,11 +80,11 @@ impl LauncherComponent {
     fn update_artifact(
         &self,
         artifact: &Update,
-        hashes: &HashMap<&str, &str>,
+        hashes: &HashMap<&str, Vec<Hash>>,
         progress_callback: &http::ProgressCallback,
     ) -> anyhow::Result<()> {
         let asset =
-            match github::find_asset(&self.release_information, artifact.artifact_name.as_str()) {
+            match release::find_asset(&self.release_information, artifact.artifact_name.as_str()) {
                 Some(asset) => asset,
                 None => return Err(LauncherAssetError::NotFound.into()),
             };
@@ -69,11 +98,11 @@ impl LauncherComponent {
 
     fn update_launcher(
         &self,
-        asset: &github::AssetInformation,
-        hashes: &HashMap<&str, &str>,
+        asset: &release::AssetInformation,
+        hashes: &HashMap<&str, Vec<Hash>>,
         progress_callback: &http::ProgressCallback,
     ) -> anyhow::Result<()> {
-        let expected_hash = hashes
+        let expected_hashes = hashes
             .get(&asset.name.as_str())
             .ok_or(LauncherAssetError::HashNotFound)?;
 
@@ -81,31 +110,89 @@ impl LauncherComponent {
         std::fs::create_dir_all(&savepath)?;
         let launcher_path = savepath.join(&asset.name);
         let download_path = launcher_path.with_extension("part");
-        http::download_file(
+        let expected_hash =
+            hash::strongest(expected_hashes).ok_or(LauncherAssetError::HashNotFound)?;
+        let actual_hash = http::download_file_hashed(
             asset.url.as_str(),
             download_path.as_path(),
+            expected_hash.algorithm,
             progress_callback,
         )?;
 
-        if sha1::digest(download_path.as_path())
-            .map_or(true, |asset_hash| asset_hash != *expected_hash)
-        {
-            return Err(LauncherAssetError::IntegrityFailure.into());
+        if let Err(e) = hash::check(asset.name.as_str(), expected_hash, &actual_hash) {
+            http::discard_download(download_path.as_path());
+            return Err(e.into());
+        }
+
+        // Don't replace the running launcher with one that can't even be loaded
+        if let Err(e) = check_loadable(download_path.as_path()) {
+            http::discard_download(download_path.as_path());
+            return Err(e);
         }
 
         let old_launcher_path = launcher_path.with_extension("old");
         std::fs::remove_file(&old_launcher_path).ok();
         std::fs::rename(&launcher_path, &old_launcher_path)?;
         std::fs::rename(download_path, &launcher_path)?;
+
+        // Loading it here doesn't prove that the loader can start it
+        let upstream_version = release::parse_version(self.release_information.tag_name.as_str())?;
+        if let Err(e) = self_test(savepath.as_path()) {
+            restore_previous(savepath.as_path())?;
+            log::write(format!("Restored the previous launcher, {upstream_version}: {e}").as_str());
+            mark_rolled_back(&upstream_version)?;
+            return Err(e);
+        }
+
+        std::fs::write(savepath.join(PENDING_MARKER), upstream_version.to_string())?;
         Ok(())
     }
 }
 
+impl LauncherComponent {
+    fn get_module_repair(
+        &self,
+        pattern: &str,
+        display_name: &str,
+        hashes: &HashMap<&str, Vec<Hash>>,
+    ) -> anyhow::Result<Option<Update>> {
+        let asset = release::find_asset(&self.release_information, pattern)
+            .ok_or(LauncherAssetError::NotFound)?;
+        let expected_hashes = hashes
+            .get(&asset.name.as_str())
+            .ok_or(LauncherAssetError::HashNotFound)?;
+
+        let launcher_path = fs::appdata_bin_path()?.join(&asset.name);
+        if hash::verify(launcher_path.as_path(), expected_hashes) {
+            return Ok(None);
+        }
+
+        Ok(Some(Update {
+            display_name: display_name.to_string(),
+            artifact_name: pattern.to_string(),
+            current: Some(Version::parse(LAUNCHER_VERSION)?),
+            upstream: release::parse_version(self.release_information.as_ref().tag_name.as_str())?,
+            download_size: asset.size,
+            requires_elevate: false,
+            requires_restart: true,
+            mandatory: self.release_information.security,
+        }))
+    }
+}
+
 impl Component for LauncherComponent {
     fn name(&self) -> &str {
         "CoD4x module"
     }
 
+    fn project(&self) -> Project {
+        Project::Launcher
+    }
+
+    fn release_notes(&self) -> Option<String> {
+        self.release_information.notes()
+    }
+
     fn get_updates(&self) -> anyhow::Result<Vec<Update>> {
         let updates = [self.get_module_update("launcher.dll", "Launcher DLL")?]
             .into_iter()
@@ -115,14 +202,33 @@ impl Component for LauncherComponent {
         Ok(updates)
     }
 
+    fn get_repairs(&self) -> anyhow::Result<Vec<Update>> {
+        let hashes_str = release::fetch_hashes(
+            &self.release_information,
+            &Project::Launcher.signature_policy(),
+        )?;
+        let hashes = release::parse_hashes(hashes_str.as_str());
+
+        let repairs = [self.get_module_repair("launcher.dll", "Launcher DLL", &hashes)?]
+            .into_iter()
+            .flatten()
+            .collect();
+
+        Ok(repairs)
+    }
+
     fn update(
         &self,
         updates: &[Update],
         status_update: &dyn Fn(String),
         progress_callback: &http::ProgressCallback,
     ) -> anyhow::Result<()> {
-        let hashes_str = github::fetch_hashes(&self.release_information)?;
-        let hashes = github::parse_hashes(hashes_str.as_str());
+        component::report_release(&self.release_information, status_update);
+        let hashes_str = release::fetch_hashes(
+            &self.release_information,
+            &Project::Launcher.signature_policy(),
+        )?;
+        let hashes = release::parse_hashes(hashes_str.as_str());
 
         for update_artifact in updates {
             status_update(format!("Downloading {}...", update_artifact.display_name));
@@ -133,10 +239,152 @@ impl Component for LauncherComponent {
     }
 }
 
+fn check_loadable(path: &Path) -> anyhow::Result<()> {
+    unsafe {
+        let module = Library::new(path).map_err(|_| LauncherAssetError::NotLoadable)?;
+        module
+            .get::<unsafe extern "C" fn()>(b"StartLauncher\0")
+            .map_err(|_| LauncherAssetError::NotLoadable)?;
+    }
+    Ok(())
+}
+
+/// Starts the game with the installed launcher.dll to check that its loader can start it, as
+/// the launcher couldn't roll itself back otherwise.
+fn self_test(bin_path: &Path) -> anyhow::Result<()> {
+    let self_test_path = bin_path.join(SELF_TEST_MARKER);
+    std::fs::remove_file(&self_test_path).ok();
+
+    let game_path = module::get_path();
+    let mut command = Command::new(&game_path);
+    command.args(["+set", SELF_TEST_CVAR, "1"]);
+    if let Some(install_dir) = game_path.parent() {
+        command.current_dir(install_dir);
+    }
+    let mut game = command.spawn()?;
+
+    let deadline = Instant::now() + SELF_TEST_TIMEOUT;
+    let status = loop {
+        if let Some(status) = game.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            game.kill().ok();
+            game.wait().ok();
+            return Err(LauncherAssetError::SelfTestTimeout.into());
+        }
+        thread::sleep(Duration::from_millis(100));
+    };
+
+    let reported_version = std::fs::read_to_string(&self_test_path);
+    std::fs::remove_file(&self_test_path).ok();
+    match (status.success(), reported_version) {
+        (true, Ok(version)) => {
+            log::write(format!("Launcher {version} passed its self-test").as_str());
+            Ok(())
+        }
+        _ => Err(LauncherAssetError::SelfTestFailed.into()),
+    }
+}
+
+/// Ends a game started by [`self_test`] as soon as the loader started the launcher, reporting
+/// its version.
+pub fn answer_self_test() {
+    if !cmdline::is_enabled(SELF_TEST_CVAR) {
+        return;
+    }
+
+    let reported = fs::appdata_bin_path().and_then(|bin_path| {
+        Ok(std::fs::write(
+            bin_path.join(SELF_TEST_MARKER),
+            LAUNCHER_VERSION,
+        )?)
+    });
+    std::process::exit(if reported.is_ok() { 0 } else { 1 });
+}
+
+/// Called first thing when the launcher starts. The first start of a freshly installed
+/// launcher.dll is watched until [`finish_boot`]; if it never got there, the previous launcher
+/// is restored and the game restarted with it.
+pub fn begin_boot() {
+    let Ok(bin_path) = fs::appdata_bin_path() else {
+        return;
+    };
+
+    let booting_path = bin_path.join(BOOTING_MARKER);
+    if booting_path.exists() {
+        // Nothing else of the broken launcher runs, not even the log setup
+        if let Ok(appdata_path) = fs::get_appdata_cod4_path() {
+            log::init(appdata_path.join("launcher.log").as_path());
+        }
+        match rollback(bin_path.as_path()) {
+            Ok(_) => {
+                if let Err(e) = process::restart(process::Privileges::User, None) {
+                    log::write(format!("{e}").as_str());
+                }
+            }
+            Err(e) => {
+                log::write(format!("Failed to restore the previous launcher: {e}").as_str());
+                std::fs::remove_file(&booting_path).ok();
+            }
+        }
+        return;
+    }
+
+    std::fs::rename(bin_path.join(PENDING_MARKER), booting_path).ok();
+}
+
+/// Marks the start of a freshly installed launcher.dll as successful, called before handing
+/// over to the game or restarting.
+pub fn finish_boot() {
+    if let Ok(bin_path) = fs::appdata_bin_path() {
+        std::fs::remove_file(bin_path.join(BOOTING_MARKER)).ok();
+    }
+}
+
+/// Puts launcher.old back in place of the version that failed to start and keeps that version
+/// from being installed again, returning it.
+fn rollback(bin_path: &Path) -> anyhow::Result<String> {
+    let booting_path = bin_path.join(BOOTING_MARKER);
+    let version = std::fs::read_to_string(&booting_path)?;
+    restore_previous(bin_path)?;
+    std::fs::remove_file(&booting_path)?;
+    log::write(format!("Restored the previous launcher, {version} failed to start").as_str());
+
+    if let Ok(version) = Version::parse(version.trim()) {
+        mark_rolled_back(&version)?;
+    }
+    Ok(version)
+}
+
+/// Moves launcher.dll aside as launcher.broken and puts launcher.old back in its place.
+fn restore_previous(bin_path: &Path) -> anyhow::Result<()> {
+    let launcher_path = bin_path.join("launcher.dll");
+    let old_launcher_path = launcher_path.with_extension("old");
+    let broken_launcher_path = launcher_path.with_extension("broken");
+    if !old_launcher_path.exists() {
+        return Err(LauncherAssetError::NoPreviousLauncher.into());
+    }
+
+    std::fs::remove_file(&broken_launcher_path).ok();
+    std::fs::rename(&launcher_path, &broken_launcher_path)?;
+    std::fs::rename(&old_launcher_path, &launcher_path)?;
+    Ok(())
+}
+
+fn mark_rolled_back(version: &Version) -> anyhow::Result<()> {
+    let mut state = UpdateState::load();
+    state.roll_back(Project::Launcher, version);
+    state.save()
+}
+
 pub enum LauncherAssetError {
     NotFound,
     HashNotFound,
-    IntegrityFailure,
+    NotLoadable,
+    SelfTestFailed,
+    SelfTestTimeout,
+    NoPreviousLauncher,
 }
 
 impl LauncherAssetError {
@@ -144,7 +392,10 @@ impl LauncherAssetError {
         match self {
             Self::NotFound => "Couldn't find launcher asset",
             Self::HashNotFound => "Couldn't find launcher asset hash",
-            Self::IntegrityFailure => "Launcher asset integrity verification failed",
+            Self::NotLoadable => "The downloaded launcher can't be loaded",
+            Self::SelfTestFailed => "The downloaded launcher failed to start",
+            Self::SelfTestTimeout => "The downloaded launcher didn't start in time",
+            Self::NoPreviousLauncher => "Couldn't find the previous launcher",
         }
     }
 }
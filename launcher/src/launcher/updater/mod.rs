@@ -1,112 +1,377 @@
 mod cod4x;
 mod component;
-pub mod github;
 mod gui;
 mod launcher;
 mod mss32;
+mod selection;
+pub mod source;
+mod state;
 pub mod updater_app;
-use crate::launcher::{msg_box, process};
+use crate::launcher::cod4x as cod4x_module;
+use crate::launcher::{cmdline, http, log, msg_box, process};
+pub use cod4x_updater::release;
 use component::{Component, ComponentUpdates, Update};
-use std::sync::Arc;
-
-fn get_updates() -> Vec<(Vec<Update>, Box<dyn Component>)> {
-    let mut components: Vec<Box<dyn Component>> = Vec::new();
-
-    match cod4x::CoD4xComponent::new() {
-        Ok(component) => components.push(Box::new(component)),
-        Err(e) => msg_box::message_box(
-            format!("Error updating CoD4x:\n{e}").as_str(),
-            "CoD4x Updater",
-        ),
-    }
-    match launcher::LauncherComponent::new() {
-        Ok(component) => components.push(Box::new(component)),
-        Err(e) => msg_box::message_box(
-            format!("Error updating launcher:\n{e}").as_str(),
-            "CoD4x Updater",
-        ),
-    }
-    match mss32::Mss32Component::new() {
-        Ok(component) => components.push(Box::new(component)),
-        Err(e) => msg_box::message_box(
-            format!("Error updating Miles Loader:\n{e}").as_str(),
-            "CoD4x Updater",
-        ),
-    }
-
-    components
-        .into_iter()
-        .filter_map(|component| match component.get_updates() {
-            Ok(updates) if !updates.is_empty() => Some((updates, component)),
-            _ => None,
-        })
-        .collect::<Vec<ComponentUpdates>>()
+pub use launcher::{answer_self_test, begin_boot, finish_boot};
+use selection::Decision;
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Time the update check may take in total before the game starts without the components that
+/// haven't answered yet.
+const UPDATE_CHECK_DEADLINE: Duration = Duration::from_secs(5);
+
+/// Cvar listing the artifacts the player deselected, passed on when restarting elevated.
+const SKIP_UPDATES_CVAR: &str = "skip_updates";
+
+type ComponentFactory = fn(Mode) -> anyhow::Result<Box<dyn Component>>;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Install newer releases.
+    Update,
+    /// Reinstall installed files that don't match the release hashes.
+    Repair,
 }
 
-fn build_updates_message(
-    updates: &[(Vec<Update>, Box<dyn Component>)],
-    needs_elevation: bool,
-) -> String {
-    let updates_string = updates
-        .iter()
-        .map(|(updates, component)| {
-            let mut component_updates = format!("{}:", component.name());
-            for update in updates {
-                let current_version = update
-                    .current
-                    .as_ref()
-                    .map_or("unknown".to_string(), |v| format!("{v}"));
-
-                component_updates += format!(
-                    "\n  - {}: {} => {}",
-                    update.display_name, current_version, update.upstream
-                )
-                .as_str();
+#[derive(Clone, Copy)]
+pub struct Options {
+    pub mode: Mode,
+    /// Install updates without asking and without the updater window, only reporting errors.
+    pub silent: bool,
+    /// Let silent updates restart the launcher with administrator rights instead of skipping
+    /// the updates that require them.
+    pub auto_elevate: bool,
+    /// Minimum time since the last update check before checking again.
+    pub check_interval: Duration,
+}
+
+enum UpdateResult {
+    Updated,
+    Failed(anyhow::Error),
+    Aborted,
+}
+
+fn update_component(
+    component: &dyn Component,
+    updates: &[Update],
+    status_report: &dyn Fn(String),
+    progress_callback: &http::ProgressCallback,
+) -> UpdateResult {
+    match component.update(updates, status_report, progress_callback) {
+        Ok(_) => UpdateResult::Updated,
+        Err(err) => match err.downcast_ref::<curl::Error>() {
+            // User abort
+            Some(curl_err) if curl_err.is_aborted_by_callback() => UpdateResult::Aborted,
+            _ => UpdateResult::Failed(err),
+        },
+    }
+}
+
+/// Installs `updates` without any window, logging the progress. Failures are reported once
+/// all components are done. Returns whether all updates were installed.
+fn run_silent(updates: &[ComponentUpdates]) -> bool {
+    let progress_callback = http::ProgressCallback::new(|_| true);
+    let status_report = |status: String| log::write(status.as_str());
+
+    let mut errors = Vec::new();
+    for (updates, component) in updates {
+        log::write(format!("Updating {}", component.name()).as_str());
+        match update_component(
+            component.as_ref(),
+            updates,
+            &status_report,
+            &progress_callback,
+        ) {
+            UpdateResult::Updated => {
+                log::write(format!("Successfully updated {}", component.name()).as_str())
+            }
+            UpdateResult::Failed(err) => {
+                let error = format!("Failed to update {}: {err}", component.name());
+                log::write(error.as_str());
+                errors.push(error);
+            }
+            UpdateResult::Aborted => {}
+        }
+    }
+
+    if !errors.is_empty() {
+        msg_box::message_box(errors.join("\n").as_str(), "CoD4x Updater");
+    }
+    errors.is_empty()
+}
+
+/// Checks all components for updates, also returning whether every one of them was checked.
+fn get_updates(mode: Mode) -> (Vec<ComponentUpdates>, bool) {
+    let factories: [(&str, ComponentFactory); 3] = [
+        ("CoD4x", |mode| {
+            Ok(Box::new(cod4x::CoD4xComponent::new(mode)?))
+        }),
+        ("launcher", |mode| {
+            Ok(Box::new(launcher::LauncherComponent::new(mode)?))
+        }),
+        ("Miles Loader", |mode| {
+            Ok(Box::new(mss32::Mss32Component::new(mode)?))
+        }),
+    ];
+
+    let (tx, rx) = mpsc::channel();
+    for (index, (name, factory)) in factories.iter().copied().enumerate() {
+        let tx = tx.clone();
+        thread::spawn(move || {
+            let updates = factory(mode).map(|component| {
+                let updates = match mode {
+                    Mode::Update => component.get_updates(),
+                    Mode::Repair => component.get_repairs(),
+                };
+                updates.map(|updates| (!updates.is_empty()).then_some((updates, component)))
+            });
+            tx.send((index, name, updates)).ok();
+        });
+    }
+    drop(tx);
+
+    let mut results = Vec::new();
+    match mode {
+        Mode::Update => {
+            let deadline = Instant::now() + UPDATE_CHECK_DEADLINE;
+            while let Some(timeout) = deadline.checked_duration_since(Instant::now()) {
+                match rx.recv_timeout(timeout) {
+                    Ok(result) => results.push(result),
+                    Err(_) => break,
+                }
+            }
+        }
+        // Hashing every file takes a while and the player asked for it
+        Mode::Repair => results.extend(rx.iter()),
+    }
+    results.sort_by_key(|(index, _, _)| *index);
+
+    for (index, (name, _)) in factories.iter().enumerate() {
+        if !results
+            .iter()
+            .any(|(result_index, _, _)| *result_index == index)
+        {
+            log::write(format!("Update check of {name} timed out, skipping it").as_str());
+        }
+    }
+
+    let mut complete = results.len() == factories.len();
+    let updates = results
+        .into_iter()
+        .filter_map(|(_, name, updates)| match updates {
+            Ok(Ok(updates)) => updates,
+            Ok(Err(e)) => {
+                log::write(format!("Update check of {name} failed: {e}").as_str());
+                complete = false;
+                None
+            }
+            Err(e) => {
+                msg_box::message_box(
+                    format!("Error updating {name}:\n{e}").as_str(),
+                    "CoD4x Updater",
+                );
+                complete = false;
+                None
             }
-            component_updates
         })
-        .collect::<Vec<String>>()
-        .join("\n");
+        .collect::<Vec<ComponentUpdates>>();
+    (updates, complete)
+}
 
+fn build_updates_message(needs_elevation: bool, mode: Mode) -> String {
+    let (title, question) = match mode {
+        Mode::Update => ("Updates available", "Select the updates to install:"),
+        Mode::Repair => ("Damaged or missing files", "Select the files to repair:"),
+    };
     format!(
-        "Updates available:\n {}\n\n{}Do you want to update?",
-        updates_string,
+        "{title}\n{}{question}",
         if needs_elevation {
-            "The update requires administrator rights\n"
+            "Some updates require administrator rights\n"
         } else {
             ""
         }
     )
 }
 
-pub fn run_updater(is_elevated: bool) -> anyhow::Result<()> {
-    let updates = get_updates();
+fn build_release_notes(updates: &[ComponentUpdates]) -> String {
+    updates
+        .iter()
+        .filter_map(|(_, component)| {
+            let notes = component.release_notes()?;
+            Some(format!("{}:\n{notes}", component.name()))
+        })
+        .collect::<Vec<String>>()
+        .join("\n\n")
+}
+
+/// Keeps the updates matching `keep`, dropping components left without updates.
+fn retain_updates(updates: &mut Vec<ComponentUpdates>, mut keep: impl FnMut(&Update) -> bool) {
+    for (component_updates, _) in updates.iter_mut() {
+        component_updates.retain(&mut keep);
+    }
+    updates.retain(|(component_updates, _)| !component_updates.is_empty());
+}
+
+/// Artifacts the player deselected before the launcher restarted elevated.
+fn get_skipped_updates() -> Vec<String> {
+    let cmdline_args: Vec<_> = std::env::args().collect();
+    cmdline::get_value(SKIP_UPDATES_CVAR, &cmdline_args)
+        .and_then(|skipped| hex::decode(skipped).ok())
+        .and_then(|skipped| String::from_utf8(skipped).ok())
+        .map(|skipped| skipped.lines().map(|name| name.to_string()).collect())
+        .unwrap_or_default()
+}
+
+pub fn run_updater(is_elevated: bool, options: Options) -> anyhow::Result<()> {
+    // Repairs and the restart after elevating always check and ignore deferred updates
+    let mut state = state::UpdateState::load();
+    let throttle = options.mode == Mode::Update && !is_elevated;
+    // The game can't start without a CoD4x DLL, so its installation is never put off
+    if throttle
+        && cod4x_module::get_module_version().is_ok()
+        && !state.is_check_due(options.check_interval)
+    {
+        log::write("Skipping the update check, the last one was too recent");
+        return Ok(());
+    }
+
+    let (mut updates, complete) = get_updates(options.mode);
+    if options.mode == Mode::Update {
+        // Not even mandatory updates, they would fail to start again
+        for (component_updates, component) in updates.iter_mut() {
+            let project = component.project();
+            component_updates.retain(|update_artifact| {
+                !state.is_rolled_back(project, &update_artifact.upstream)
+            });
+        }
+        updates.retain(|(component_updates, _)| !component_updates.is_empty());
+    }
+
+    if throttle {
+        // Deferred components are only offered their mandatory updates, and silent updates
+        // don't install them either
+        for (component_updates, component) in updates.iter_mut() {
+            let project = component.project();
+            component_updates.retain(|update_artifact| {
+                update_artifact.mandatory || !state.is_deferred(project, &update_artifact.upstream)
+            });
+        }
+        updates.retain(|(component_updates, _)| !component_updates.is_empty());
+
+        // Failed, incomplete and declined checks are repeated on the next start
+        if complete && updates.is_empty() {
+            state.record_check();
+            if let Err(e) = state.save() {
+                log::write(format!("Failed to save the update state: {e}").as_str());
+            }
+        }
+    }
 
     if updates.is_empty() {
+        if options.mode == Mode::Repair && !options.silent {
+            msg_box::message_box("All CoD4x files are intact.", "CoD4x Updater");
+        }
         return Ok(());
     }
 
-    let needs_elevation = !is_elevated
-        && updates.iter().any(|(component, _)| {
-            component
-                .iter()
-                .any(|update_artifact| update_artifact.requires_elevate)
+    let requires_elevation = |updates: &[ComponentUpdates]| {
+        !is_elevated
+            && updates.iter().any(|(component, _)| {
+                component
+                    .iter()
+                    .any(|update_artifact| update_artifact.requires_elevate)
+            })
+    };
+
+    if options.silent && !options.auto_elevate && requires_elevation(&updates) {
+        log::write("Skipping updates that require administrator rights");
+        retain_updates(&mut updates, |update_artifact| {
+            !update_artifact.requires_elevate
         });
+    }
 
-    let update_message = build_updates_message(&updates, needs_elevation);
-    let params = nwg::MessageParams {
-        title: "CoD4x Updater",
-        content: update_message.as_str(),
-        buttons: nwg::MessageButtons::YesNo,
-        icons: nwg::MessageIcons::Question,
-    };
+    let mut skipped_updates = Vec::new();
+    if is_elevated {
+        // The player already chose before the restart
+        let skipped = get_skipped_updates();
+        retain_updates(&mut updates, |update_artifact| {
+            !skipped.contains(&update_artifact.artifact_name)
+        });
+    } else if !options.silent {
+        let update_message = build_updates_message(requires_elevation(&updates), options.mode);
+        let release_notes = build_release_notes(&updates);
+        let artifact_names = updates
+            .iter()
+            .flat_map(|(component, _)| component.iter())
+            .map(|update_artifact| update_artifact.artifact_name.clone())
+            .collect::<Vec<String>>();
+        let deferrable = options.mode == Mode::Update
+            && updates
+                .iter()
+                .flat_map(|(component, _)| component.iter())
+                .any(|update_artifact| !update_artifact.mandatory);
 
-    if !is_elevated && nwg::message(&params) != nwg::MessageChoice::Yes {
+        match selection::select_updates(&updates, &update_message, &release_notes, deferrable)? {
+            Decision::Install(selected) => {
+                let mut selected = selected.into_iter();
+                retain_updates(&mut updates, |_| selected.next().unwrap_or(false));
+            }
+            Decision::Dismiss => return Ok(()),
+            decision => {
+                for (component_updates, component) in &updates {
+                    let Some(update_artifact) = component_updates
+                        .iter()
+                        .find(|update_artifact| !update_artifact.mandatory)
+                    else {
+                        continue;
+                    };
+                    match decision {
+                        Decision::SkipVersion => {
+                            state.skip_version(component.project(), &update_artifact.upstream)
+                        }
+                        _ => state.snooze(component.project()),
+                    }
+                }
+                if let Err(e) = state.save() {
+                    log::write(format!("Failed to save the update state: {e}").as_str());
+                }
+                retain_updates(&mut updates, |update_artifact| update_artifact.mandatory);
+            }
+        }
+
+        skipped_updates = artifact_names
+            .into_iter()
+            .filter(|artifact_name| {
+                !updates.iter().any(|(component, _)| {
+                    component
+                        .iter()
+                        .any(|update_artifact| update_artifact.artifact_name == *artifact_name)
+                })
+            })
+            .collect();
+    }
+
+    if updates.is_empty() {
         return Ok(());
     }
+    let needs_elevation = requires_elevation(&updates);
 
     if needs_elevation {
-        process::restart(process::Privileges::Admin, Some("+set elevated 1"))?;
+        launcher::finish_boot();
+        let mut params = cmdline::get_elevated_params();
+        if !skipped_updates.is_empty() {
+            let skipped = hex::encode(skipped_updates.join("\n"));
+            params += format!(" +set {SKIP_UPDATES_CVAR} {skipped}").as_str();
+        }
+        // Silent updates go on without administrator rights once the elevated instance is done,
+        // or without the updates if it failed
+        match process::elevate(params.as_str(), options.silent) {
+            Err(e) if options.silent => {
+                log::write(format!("Failed to update with administrator rights: {e}").as_str())
+            }
+            result => result?,
+        }
         return Ok(());
     }
 
@@ -117,18 +382,34 @@ pub fn run_updater(is_elevated: bool) -> anyhow::Result<()> {
                 .any(|update_artifact| update_artifact.requires_restart)
         });
 
-    gui::run_gui(Arc::new(updates))?;
+    let installed = match options.silent {
+        true => run_silent(&updates),
+        false => {
+            gui::run_gui(Arc::new(updates))?;
+            true
+        }
+    };
 
     if is_elevated {
-        msg_box::message_box("Update installed, restart the game now.", "CoD4x Updater");
-        std::process::exit(0);
+        match options.silent {
+            // The instance waiting for this one starts the game without administrator rights
+            true => std::process::exit(if installed { 0 } else { 1 }),
+            false => {
+                msg_box::message_box("Update installed, restart the game now.", "CoD4x Updater");
+                std::process::exit(0);
+            }
+        }
     }
 
     if needs_restart {
-        msg_box::message_box(
-            "Update installed, the game will restart now.",
-            "CoD4x Updater",
-        );
+        match options.silent {
+            true => log::write("Restarting to finish the update"),
+            false => msg_box::message_box(
+                "Update installed, the game will restart now.",
+                "CoD4x Updater",
+            ),
+        }
+        launcher::finish_boot();
         process::restart(process::Privileges::User, None)?;
     }
 
@@ -1,8 +1,10 @@
-use super::component::{Component, Update};
+use super::component::{self, Component, Update};
+use super::Mode;
+use crate::launcher::hash::{self, Hash};
 use crate::launcher::http;
 use crate::launcher::module;
-use crate::launcher::sha1;
-use crate::launcher::updater::github;
+use crate::launcher::updater::release;
+use crate::launcher::updater::source::{self, Project};
 use core::ffi::{c_char, CStr};
 use semver::Version;
 use std::collections::HashMap;
@@ -11,15 +13,22 @@ use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
 use std::sync::Arc;
 
 pub struct Mss32Component {
-    release_information: Arc<github::ReleaseInformation>,
+    release_information: Arc<release::ReleaseInformation>,
 }
 
 impl Mss32Component {
-    pub fn new() -> anyhow::Result<Self> {
+    /// Repairs verify the installed loader against the release it comes from.
+    pub fn new(mode: Mode) -> anyhow::Result<Self> {
+        let release_information = match mode {
+            Mode::Update => source::fetch_release_information(Project::Mss32)?,
+            Mode::Repair => source::fetch_installed_release_information(
+                Project::Mss32,
+                Self::get_module_version().as_ref(),
+            )?,
+        };
+
         Ok(Self {
-            release_information: Arc::new(github::fetch_release_information(
-                "callofduty4x/CoD4x-mss",
-            )?),
+            release_information: Arc::new(release_information),
         })
     }
 
@@ -55,7 +64,7 @@ impl Mss32Component {
         pattern: &str,
         display_name: &str,
     ) -> anyhow::Result<Option<Update>> {
-        let upstream = Version::parse(self.release_information.as_ref().tag_name.as_str())?;
+        let upstream = release::parse_version(self.release_information.as_ref().tag_name.as_str())?;
         let current = Self::get_module_version();
 
         let needs_update = current.as_ref().is_none_or(|c| upstream > *c);
@@ -65,8 +74,11 @@ impl Mss32Component {
                 artifact_name: pattern.to_string(),
                 current,
                 upstream,
+                download_size: release::find_asset(&self.release_information, pattern)
+                    .and_then(|asset| asset.size),
                 requires_elevate: true,
                 requires_restart: true,
+                mandatory: self.release_information.security,
             }))
         } else {
             Ok(None)
@@ -76,11 +88,11 @@ impl Mss32Component {
     fn update_artifact(
         &self,
         artifact: &Update,
-        hashes: &HashMap<&str, &str>,
+        hashes: &HashMap<&str, Vec<Hash>>,
         progress_callback: &http::ProgressCallback,
     ) -> anyhow::Result<()> {
         let asset =
-            match github::find_asset(&self.release_information, artifact.artifact_name.as_str()) {
+            match release::find_asset(&self.release_information, artifact.artifact_name.as_str()) {
                 Some(asset) => asset,
                 None => return Err(Mss32AssetError::NotFound.into()),
             };
@@ -94,11 +106,11 @@ impl Mss32Component {
 
     fn update_mss32(
         &self,
-        asset: &github::AssetInformation,
-        hashes: &HashMap<&str, &str>,
+        asset: &release::AssetInformation,
+        hashes: &HashMap<&str, Vec<Hash>>,
         progress_callback: &http::ProgressCallback,
     ) -> anyhow::Result<()> {
-        let expected_hash = hashes
+        let expected_hashes = hashes
             .get(&asset.name.as_str())
             .ok_or(Mss32AssetError::HashNotFound)?;
 
@@ -107,16 +119,18 @@ impl Mss32Component {
 
         let download_path = install_dir.join(&asset.name).with_extension("part");
 
-        http::download_file(
+        let expected_hash =
+            hash::strongest(expected_hashes).ok_or(Mss32AssetError::HashNotFound)?;
+        let actual_hash = http::download_file_hashed(
             asset.url.as_str(),
             download_path.as_path(),
+            expected_hash.algorithm,
             progress_callback,
         )?;
 
-        if sha1::digest(download_path.as_path())
-            .map_or(true, |asset_hash| asset_hash != *expected_hash)
-        {
-            return Err(Mss32AssetError::IntegrityFailure.into());
+        if let Err(e) = hash::check(asset.name.as_str(), expected_hash, &actual_hash) {
+            http::discard_download(download_path.as_path());
+            return Err(e.into());
         }
 
         let mss_path = install_dir.join(asset.name.as_str());
@@ -130,11 +144,51 @@ impl Mss32Component {
     }
 }
 
+impl Mss32Component {
+    fn get_module_repair(
+        &self,
+        pattern: &str,
+        display_name: &str,
+        hashes: &HashMap<&str, Vec<Hash>>,
+    ) -> anyhow::Result<Option<Update>> {
+        let asset = release::find_asset(&self.release_information, pattern)
+            .ok_or(Mss32AssetError::NotFound)?;
+        let expected_hashes = hashes
+            .get(&asset.name.as_str())
+            .ok_or(Mss32AssetError::HashNotFound)?;
+
+        let module_path = module::get_path();
+        let install_dir = module_path.parent().ok_or(Mss32AssetError::WriteFailure)?;
+        if hash::verify(install_dir.join(&asset.name).as_path(), expected_hashes) {
+            return Ok(None);
+        }
+
+        Ok(Some(Update {
+            display_name: display_name.to_string(),
+            artifact_name: pattern.to_string(),
+            current: Self::get_module_version(),
+            upstream: release::parse_version(self.release_information.as_ref().tag_name.as_str())?,
+            download_size: asset.size,
+            requires_elevate: true,
+            requires_restart: true,
+            mandatory: self.release_information.security,
+        }))
+    }
+}
+
 impl Component for Mss32Component {
     fn name(&self) -> &str {
         "Miles Loader"
     }
 
+    fn project(&self) -> Project {
+        Project::Mss32
+    }
+
+    fn release_notes(&self) -> Option<String> {
+        self.release_information.notes()
+    }
+
     fn get_updates(&self) -> anyhow::Result<Vec<Update>> {
         let updates = [self.get_module_update("mss32.dll", "Miles Loader")?]
             .into_iter()
@@ -144,14 +198,33 @@ impl Component for Mss32Component {
         Ok(updates)
     }
 
+    fn get_repairs(&self) -> anyhow::Result<Vec<Update>> {
+        let hashes_str = release::fetch_hashes(
+            &self.release_information,
+            &Project::Mss32.signature_policy(),
+        )?;
+        let hashes = release::parse_hashes(hashes_str.as_str());
+
+        let repairs = [self.get_module_repair("mss32.dll", "Miles Loader", &hashes)?]
+            .into_iter()
+            .flatten()
+            .collect();
+
+        Ok(repairs)
+    }
+
     fn update(
         &self,
         updates: &[Update],
         status_update: &dyn Fn(String),
         progress_callback: &http::ProgressCallback,
     ) -> anyhow::Result<()> {
-        let hashes_str = github::fetch_hashes(&self.release_information)?;
-        let hashes = github::parse_hashes(hashes_str.as_str());
+        component::report_release(&self.release_information, status_update);
+        let hashes_str = release::fetch_hashes(
+            &self.release_information,
+            &Project::Mss32.signature_policy(),
+        )?;
+        let hashes = release::parse_hashes(hashes_str.as_str());
 
         for update_artifact in updates {
             status_update(format!("Downloading {}...", update_artifact.display_name));
@@ -165,7 +238,6 @@ impl Component for Mss32Component {
 pub enum Mss32AssetError {
     NotFound,
     HashNotFound,
-    IntegrityFailure,
     WriteFailure,
 }
 
@@ -174,7 +246,6 @@ impl Mss32AssetError {
         match self {
             Self::NotFound => "Couldn't find Miles Loader asset",
             Self::HashNotFound => "Couldn't find Miles Loader asset hash",
-            Self::IntegrityFailure => "Miles Loader asset integrity verification failed",
             Self::WriteFailure => "Failed to write Miles Loader",
         }
     }
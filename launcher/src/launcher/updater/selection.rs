@@ -0,0 +1,327 @@
+use super::component::{ComponentUpdates, Update};
+use super::gui;
+use super::updater_app::Updater;
+
+use nwg::NativeUi;
+
+use std::cell::RefCell;
+use std::ops::Deref;
+use std::rc::Rc;
+
+/// What the player chose in the update dialog.
+pub enum Decision {
+    /// Install the updates whose flag is set, in the order of the updates.
+    Install(Vec<bool>),
+    /// Offer the updates again after a while.
+    Snooze,
+    /// Don't offer these versions again.
+    SkipVersion,
+    /// The dialog was closed, ask again on the next start.
+    Dismiss,
+}
+
+/// Asks which of `updates` to install, showing `message` and the release `notes` above the
+/// list. Unless `deferrable`, the player can't snooze or skip the offered versions.
+pub fn select_updates(
+    updates: &[ComponentUpdates],
+    message: &str,
+    notes: &str,
+    deferrable: bool,
+) -> anyhow::Result<Decision> {
+    let entries = updates
+        .iter()
+        .flat_map(|(updates, component)| {
+            updates
+                .iter()
+                .map(move |update| (format_entry(component.name(), update), update.mandatory))
+        })
+        .collect();
+
+    gui::init()?;
+    let selection =
+        UpdateSelection::build_ui(UpdateSelection::new(message, notes, entries, deferrable))?;
+    nwg::dispatch_thread_events();
+
+    let decision = selection.decision.borrow_mut().take();
+    Ok(decision.unwrap_or(Decision::Dismiss))
+}
+
+fn format_entry(component_name: &str, update: &Update) -> String {
+    let current_version = update
+        .current
+        .as_ref()
+        .map_or("unknown".to_string(), |v| format!("{v}"));
+
+    let mut entry = format!(
+        "{component_name}: {} {current_version} => {}",
+        update.display_name, update.upstream
+    );
+    if let Some(download_size) = update.download_size {
+        entry += format!(" ({})", Updater::format_size(download_size)).as_str();
+    }
+    if update.mandatory {
+        entry += " (required)";
+    }
+    entry
+}
+
+pub struct UpdateSelection {
+    message: String,
+    notes: String,
+    /// Label and mandatory flag of every update, in the order of the updates.
+    entries: Vec<(String, bool)>,
+    deferrable: bool,
+    /// Set once the player clicks one of the buttons.
+    decision: RefCell<Option<Decision>>,
+
+    window: nwg::Window,
+    layout: nwg::FlexboxLayout,
+    message_label: nwg::Label,
+    notes_box: nwg::TextBox,
+    checkboxes: Vec<nwg::CheckBox>,
+    buttons_layout: nwg::FlexboxLayout,
+    update_button: nwg::Button,
+    snooze_button: nwg::Button,
+    skip_version_button: nwg::Button,
+}
+
+impl UpdateSelection {
+    fn new(message: &str, notes: &str, entries: Vec<(String, bool)>, deferrable: bool) -> Self {
+        Self {
+            message: message.to_string(),
+            // Edit controls only break lines on CRLF
+            notes: notes.replace('\n', "\r\n"),
+            entries,
+            deferrable,
+            decision: RefCell::new(None),
+            window: Default::default(),
+            layout: Default::default(),
+            message_label: Default::default(),
+            notes_box: Default::default(),
+            checkboxes: Vec::new(),
+            buttons_layout: Default::default(),
+            update_button: Default::default(),
+            snooze_button: Default::default(),
+            skip_version_button: Default::default(),
+        }
+    }
+
+    fn decide(&self, decision: Decision) {
+        *self.decision.borrow_mut() = Some(decision);
+        self.window.close();
+    }
+
+    fn confirm(&self) {
+        let selected = self
+            .checkboxes
+            .iter()
+            .map(|checkbox| checkbox.check_state() == nwg::CheckBoxState::Checked)
+            .collect();
+        self.decide(Decision::Install(selected));
+    }
+}
+
+pub struct UpdateSelectionGui {
+    inner: Rc<UpdateSelection>,
+    default_handler: RefCell<Option<nwg::EventHandler>>,
+}
+
+impl nwg::NativeUi<UpdateSelectionGui> for UpdateSelection {
+    fn build_ui(mut data: UpdateSelection) -> Result<UpdateSelectionGui, nwg::NwgError> {
+        use nwg::Event as E;
+
+        const WINDOW_WIDTH: i32 = 450;
+        const MESSAGE_HEIGHT: i32 = 50;
+        const NOTES_HEIGHT: i32 = 150;
+        const CHECKBOX_HEIGHT: i32 = 25;
+        const BUTTONS_HEIGHT: i32 = 45;
+
+        let has_notes = !data.notes.is_empty();
+        let window_height = MESSAGE_HEIGHT
+            + if has_notes { NOTES_HEIGHT } else { 0 }
+            + CHECKBOX_HEIGHT * data.entries.len() as i32
+            + BUTTONS_HEIGHT
+            + 20;
+
+        let screen_width = nwg::Monitor::width();
+        let screen_height = nwg::Monitor::height();
+
+        let center_x = (screen_width - WINDOW_WIDTH) / 2;
+        let center_y = (screen_height - window_height) / 2;
+
+        nwg::Window::builder()
+            .flags(nwg::WindowFlags::WINDOW | nwg::WindowFlags::VISIBLE)
+            .size((WINDOW_WIDTH, window_height))
+            .position((center_x, center_y))
+            .title("CoD4x Updater")
+            .build(&mut data.window)?;
+
+        // Controls
+        nwg::Label::builder()
+            .text(data.message.as_str())
+            .parent(&data.window)
+            .build(&mut data.message_label)?;
+
+        nwg::TextBox::builder()
+            .flags(
+                nwg::TextBoxFlags::VSCROLL
+                    | nwg::TextBoxFlags::AUTOVSCROLL
+                    | match has_notes {
+                        true => nwg::TextBoxFlags::VISIBLE,
+                        false => nwg::TextBoxFlags::empty(),
+                    },
+            )
+            .text(data.notes.as_str())
+            .readonly(true)
+            .parent(&data.window)
+            .build(&mut data.notes_box)?;
+
+        for (label, mandatory) in &data.entries {
+            let mut checkbox = nwg::CheckBox::default();
+            nwg::CheckBox::builder()
+                .text(label.as_str())
+                .check_state(nwg::CheckBoxState::Checked)
+                .enabled(!mandatory)
+                .parent(&data.window)
+                .build(&mut checkbox)?;
+            data.checkboxes.push(checkbox);
+        }
+
+        nwg::Button::builder()
+            .text("Update")
+            .parent(&data.window)
+            .build(&mut data.update_button)?;
+
+        let defer_button_flags = match data.deferrable {
+            true => nwg::ButtonFlags::VISIBLE,
+            false => nwg::ButtonFlags::empty(),
+        };
+
+        nwg::Button::builder()
+            .text("Remind me later")
+            .flags(defer_button_flags)
+            .parent(&data.window)
+            .build(&mut data.snooze_button)?;
+
+        nwg::Button::builder()
+            .text("Skip this version")
+            .flags(defer_button_flags)
+            .parent(&data.window)
+            .build(&mut data.skip_version_button)?;
+
+        // Wrap-up
+        let ui = UpdateSelectionGui {
+            inner: Rc::new(data),
+            default_handler: Default::default(),
+        };
+
+        // Events
+        let evt_ui = Rc::downgrade(&ui.inner);
+
+        let handle_events = move |evt, _evt_data, handle: nwg::ControlHandle| {
+            if let Some(ui) = evt_ui.upgrade() {
+                match evt {
+                    E::OnButtonClick if handle == ui.update_button => {
+                        UpdateSelection::confirm(&ui);
+                    }
+                    E::OnButtonClick if handle == ui.snooze_button => {
+                        UpdateSelection::decide(&ui, Decision::Snooze);
+                    }
+                    E::OnButtonClick if handle == ui.skip_version_button => {
+                        UpdateSelection::decide(&ui, Decision::SkipVersion);
+                    }
+                    E::OnWindowClose if handle == ui.window => {
+                        nwg::stop_thread_dispatch();
+                    }
+                    _ => {}
+                }
+            }
+        };
+
+        *ui.default_handler.borrow_mut() = Some(nwg::full_bind_event_handler(
+            &ui.window.handle,
+            handle_events,
+        ));
+
+        use nwg::stretch::{
+            geometry::Size,
+            style::{Dimension as D, FlexDirection, JustifyContent},
+        };
+
+        nwg::FlexboxLayout::builder()
+            .parent(&ui.window)
+            .flex_direction(FlexDirection::Row)
+            .justify_content(JustifyContent::FlexEnd)
+            .child(&ui.update_button)
+            .child_size(Size {
+                width: D::Points(80.0),
+                height: D::Points(25.0),
+            })
+            .child(&ui.snooze_button)
+            .child_size(Size {
+                width: D::Points(if ui.deferrable { 130.0 } else { 0.0 }),
+                height: D::Points(25.0),
+            })
+            .child(&ui.skip_version_button)
+            .child_size(Size {
+                width: D::Points(if ui.deferrable { 130.0 } else { 0.0 }),
+                height: D::Points(25.0),
+            })
+            .build_partial(&ui.buttons_layout)?;
+
+        let mut layout = nwg::FlexboxLayout::builder()
+            .parent(&ui.window)
+            .flex_direction(FlexDirection::Column)
+            // Message
+            .child(&ui.message_label)
+            .child_size(Size {
+                width: D::Auto,
+                height: D::Points(MESSAGE_HEIGHT as f32),
+            });
+
+        if has_notes {
+            // Release notes
+            layout = layout.child(&ui.notes_box).child_size(Size {
+                width: D::Auto,
+                height: D::Points(NOTES_HEIGHT as f32),
+            });
+        }
+
+        // One checkbox per update
+        for checkbox in &ui.checkboxes {
+            layout = layout.child(checkbox).child_size(Size {
+                width: D::Auto,
+                height: D::Points(CHECKBOX_HEIGHT as f32),
+            });
+        }
+
+        layout
+            // Buttons
+            .child_layout(&ui.buttons_layout)
+            .child_size(Size {
+                width: D::Auto,
+                height: D::Points(25.0),
+            })
+            .build(&ui.layout)?;
+
+        Ok(ui)
+    }
+}
+
+impl Drop for UpdateSelectionGui {
+    /// To make sure that everything is freed without issues, the default handler must be unbound.
+    fn drop(&mut self) {
+        let handler = self.default_handler.borrow();
+        if let Some(h) = handler.as_ref() {
+            nwg::unbind_event_handler(h);
+        }
+    }
+}
+
+impl Deref for UpdateSelectionGui {
+    type Target = UpdateSelection;
+
+    fn deref(&self) -> &UpdateSelection {
+        &self.inner
+    }
+}
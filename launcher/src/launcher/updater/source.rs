@@ -0,0 +1,108 @@
+use crate::launcher::config::{self, SourceConfig};
+use crate::launcher::filesystem as fs;
+use crate::launcher::log;
+use cod4x_updater::github::{GitHubDownloadSource, GitHubSource};
+use cod4x_updater::local::LocalDirectorySource;
+use cod4x_updater::mirror::MirrorSource;
+use cod4x_updater::release::{ReleaseInformation, SignaturePolicy};
+pub use cod4x_updater::source::{FallbackSource, ReleaseSource};
+use semver::Version;
+
+/// Projects whose releases the launcher consumes.
+#[derive(Clone, Copy)]
+pub enum Project {
+    CoD4x,
+    Launcher,
+    Mss32,
+}
+
+impl Project {
+    /// Key of the project in the `sources` section of the launcher configuration.
+    pub fn config_key(&self) -> &str {
+        match self {
+            Self::CoD4x => "cod4x",
+            Self::Launcher => "launcher",
+            Self::Mss32 => "mss32",
+        }
+    }
+
+    /// Releases after the last unsigned one of each project must be signed, so a missing
+    /// signature can't be passed off as an old release.
+    pub fn signature_policy(&self) -> SignaturePolicy {
+        match self {
+            Self::CoD4x => SignaturePolicy::After(Version::new(21, 3, 0)),
+            Self::Launcher => SignaturePolicy::After(Version::new(1, 1, 2)),
+            Self::Mss32 => SignaturePolicy::After(Version::new(1, 0, 0)),
+        }
+    }
+
+    fn github_repository(&self) -> &str {
+        match self {
+            Self::CoD4x => "callofduty4x/CoD4x_Client_pub",
+            Self::Launcher => "callofduty4x/CoD4x-launcher",
+            Self::Mss32 => "callofduty4x/CoD4x-mss",
+        }
+    }
+}
+
+/// Creates the chain of release sources configured for `project`. Without configuration the
+/// GitHub API is tried first, then the github.com download pages.
+pub fn create(project: Project) -> FallbackSource {
+    let sources = match config::get().sources(project.config_key()) {
+        Some(sources) => sources.iter().map(create_source).collect(),
+        None => vec![
+            create_source(&SourceConfig::GitHub {
+                repository: project.github_repository().to_string(),
+            }),
+            create_source(&SourceConfig::GitHubDownload {
+                repository: project.github_repository().to_string(),
+            }),
+        ],
+    };
+
+    FallbackSource::new(sources)
+}
+
+fn create_source(source_config: &SourceConfig) -> Box<dyn ReleaseSource> {
+    match source_config {
+        SourceConfig::GitHub { repository } => Box::new(GitHubSource::new(
+            repository,
+            fs::appdata_cache_path().ok().as_deref(),
+            config::get().github_token().as_deref(),
+        )),
+        SourceConfig::GitHubDownload { repository } => {
+            Box::new(GitHubDownloadSource::new(repository))
+        }
+        SourceConfig::Mirror { url } => Box::new(MirrorSource::new(url)),
+        SourceConfig::Local { path } => Box::new(LocalDirectorySource::new(path)),
+    }
+}
+
+/// Fetches the release information of `project` on its configured channel from the first of its
+/// sources that answers.
+pub fn fetch_release_information(project: Project) -> anyhow::Result<ReleaseInformation> {
+    create(project).fetch_release_information(config::get().channel(project.config_key()))
+}
+
+/// Fetches the release of `project` the installed `version` comes from, so that repairs don't
+/// replace an older version the player kept. Falls back to the release of the configured channel
+/// if the sources don't list the installed version.
+pub fn fetch_installed_release_information(
+    project: Project,
+    version: Option<&Version>,
+) -> anyhow::Result<ReleaseInformation> {
+    if let Some(version) = version {
+        match create(project).fetch_release_by_version(version) {
+            Ok(release_information) => return Ok(release_information),
+            Err(e) => log::write(
+                format!(
+                    "Couldn't find release {version} of {}, using the newest one: {e}",
+                    project.config_key()
+                )
+                .as_str(),
+            ),
+        }
+    }
+
+    fetch_release_information(project)
+}
@@ -0,0 +1,158 @@
+use super::source::Project;
+use crate::launcher::filesystem as fs;
+use cod4x_updater::time::unix_time;
+use semver::Version;
+use std::collections::HashMap;
+use std::time::Duration;
+
+const STATE_FILE_NAME: &str = "update-state.json";
+
+/// How long "Remind me later" suppresses the update prompt of a component.
+pub const SNOOZE_DURATION: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Default)]
+struct ComponentState {
+    skipped_version: Option<Version>,
+    /// Version that failed to start and was rolled back, never installed again.
+    rolled_back_version: Option<Version>,
+    /// Unix time until which the component isn't offered.
+    snoozed_until: Option<u64>,
+}
+
+/// Decisions the player made about update prompts and the time of the last update check, kept
+/// in the CoD4 appdata directory across launches. Components are keyed like in the `sources`
+/// section of the configuration.
+#[derive(Default)]
+pub struct UpdateState {
+    /// Unix time of the last update check.
+    last_check: Option<u64>,
+    components: HashMap<String, ComponentState>,
+}
+
+impl UpdateState {
+    /// Loads the state, starting over if it's missing or unreadable.
+    pub fn load() -> Self {
+        let Some(state_json) = fs::get_appdata_cod4_path()
+            .ok()
+            .and_then(|path| std::fs::read_to_string(path.join(STATE_FILE_NAME)).ok())
+            .and_then(|state_str| json::from_str::<json::Value>(state_str.as_str()).ok())
+        else {
+            return Self::default();
+        };
+
+        let last_check = state_json
+            .pointer("/last_check")
+            .and_then(|last_check| last_check.as_u64());
+
+        let components = state_json
+            .pointer("/components")
+            .and_then(|components_json| components_json.as_object())
+            .into_iter()
+            .flatten()
+            .map(|(component, component_json)| {
+                let component_state = ComponentState {
+                    skipped_version: component_json
+                        .pointer("/skipped_version")
+                        .and_then(|version| version.as_str())
+                        .and_then(|version| Version::parse(version).ok()),
+                    rolled_back_version: component_json
+                        .pointer("/rolled_back_version")
+                        .and_then(|version| version.as_str())
+                        .and_then(|version| Version::parse(version).ok()),
+                    snoozed_until: component_json
+                        .pointer("/snoozed_until")
+                        .and_then(|snoozed_until| snoozed_until.as_u64()),
+                };
+                (component.clone(), component_state)
+            })
+            .collect();
+
+        Self {
+            last_check,
+            components,
+        }
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let components_json = self
+            .components
+            .iter()
+            .map(|(component, component_state)| {
+                let component_json = json::json!({
+                    "skipped_version": component_state
+                        .skipped_version
+                        .as_ref()
+                        .map(|version| version.to_string()),
+                    "rolled_back_version": component_state
+                        .rolled_back_version
+                        .as_ref()
+                        .map(|version| version.to_string()),
+                    "snoozed_until": component_state.snoozed_until,
+                });
+                (component.clone(), component_json)
+            })
+            .collect::<json::Map<String, json::Value>>();
+
+        let state_json = json::json!({
+            "last_check": self.last_check,
+            "components": components_json,
+        });
+
+        let path = fs::get_appdata_cod4_path()?.join(STATE_FILE_NAME);
+        std::fs::write(path, state_json.to_string())?;
+        Ok(())
+    }
+
+    /// Whether at least `interval` passed since the last update check. A last check in the
+    /// future, e.g. after the clock was changed, doesn't hold back the next one.
+    pub fn is_check_due(&self, interval: Duration) -> bool {
+        let now = unix_time();
+        self.last_check
+            .is_none_or(|last_check| last_check > now || now - last_check >= interval.as_secs())
+    }
+
+    pub fn record_check(&mut self) {
+        self.last_check = Some(unix_time());
+    }
+
+    /// Whether the player asked not to be offered `version` of `project` right now.
+    pub fn is_deferred(&self, project: Project, version: &Version) -> bool {
+        self.components
+            .get(project.config_key())
+            .is_some_and(|component_state| {
+                component_state.skipped_version.as_ref() == Some(version)
+                    || component_state
+                        .snoozed_until
+                        .is_some_and(|snoozed_until| snoozed_until > unix_time())
+            })
+    }
+
+    /// Whether `version` of `project` was rolled back after failing to start. Unlike deferred
+    /// versions this also holds for mandatory updates, which would otherwise be installed and
+    /// rolled back over and over.
+    pub fn is_rolled_back(&self, project: Project, version: &Version) -> bool {
+        self.components
+            .get(project.config_key())
+            .is_some_and(|component_state| {
+                component_state.rolled_back_version.as_ref() == Some(version)
+            })
+    }
+
+    pub fn roll_back(&mut self, project: Project, version: &Version) {
+        self.component_mut(project).rolled_back_version = Some(version.clone());
+    }
+
+    pub fn skip_version(&mut self, project: Project, version: &Version) {
+        self.component_mut(project).skipped_version = Some(version.clone());
+    }
+
+    pub fn snooze(&mut self, project: Project) {
+        self.component_mut(project).snoozed_until = Some(unix_time() + SNOOZE_DURATION.as_secs());
+    }
+
+    fn component_mut(&mut self, project: Project) -> &mut ComponentState {
+        self.components
+            .entry(project.config_key().to_string())
+            .or_default()
+    }
+}
@@ -1,10 +1,12 @@
-use super::component::ComponentUpdates;
+use super::component::{ComponentUpdates, Update};
+use super::UpdateResult;
 use crate::launcher::http;
 use crate::launcher::wstring;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 use std::sync::{mpsc, Arc, Mutex};
 use std::thread::{self, JoinHandle};
+use std::time::Duration;
 use winapi::shared::minwindef::{LPARAM, LRESULT, UINT, WPARAM};
 use winapi::shared::windef::HWND;
 
@@ -17,6 +19,70 @@ const BLUE: &str = "$[50,50,255]";
 const ORANGE: &str = "$[255,130,40]";
 const RESET: &str = "$[reset]";
 
+pub enum ProgressUpdate {
+    /// Progress of the current file and of the whole update, if all sizes are known.
+    Download(http::ProgressEvent, Option<f64>),
+    Finish,
+}
+
+/// Progress of the whole update run, measured against the asset sizes of the planned updates.
+/// Each component counts with its planned size once done, even if it downloaded less (deltas)
+/// or failed.
+struct OverallProgress {
+    planned_total: Option<u64>,
+    completed: u64,
+    component_planned: u64,
+    component_downloaded: u64,
+    file_downloaded: u64,
+}
+
+impl OverallProgress {
+    fn new(updates: &[ComponentUpdates]) -> Self {
+        let planned_total = updates
+            .iter()
+            .flat_map(|(updates, _)| updates)
+            .map(|update| update.download_size)
+            .sum::<Option<u64>>();
+
+        Self {
+            planned_total,
+            completed: 0,
+            component_planned: 0,
+            component_downloaded: 0,
+            file_downloaded: 0,
+        }
+    }
+
+    fn start_component(&mut self, updates: &[Update]) {
+        self.component_planned = updates
+            .iter()
+            .filter_map(|update| update.download_size)
+            .sum();
+        self.component_downloaded = 0;
+        self.file_downloaded = 0;
+    }
+
+    fn finish_component(&mut self) {
+        self.completed += self.component_planned;
+        self.start_component(&[]);
+    }
+
+    fn start_file(&mut self) {
+        self.component_downloaded += self.file_downloaded;
+        self.file_downloaded = 0;
+    }
+
+    fn update(&mut self, event: &http::ProgressEvent) -> Option<f64> {
+        self.file_downloaded = event.downloaded;
+
+        let downloaded = self.completed
+            + (self.component_downloaded + self.file_downloaded).min(self.component_planned);
+        self.planned_total
+            .filter(|planned_total| *planned_total > 0)
+            .map(|planned_total| (downloaded as f64 / planned_total as f64 * 100.0).min(100.0))
+    }
+}
+
 pub struct Updater {
     pub updates: Arc<Vec<ComponentUpdates>>,
 
@@ -30,9 +96,11 @@ pub struct Updater {
     pub scrollbuffer_notice: nwg::Notice,
 
     pub progress_bar: nwg::ProgressBar,
+    pub progress_marquee: Cell<bool>,
+    pub progress_label: nwg::Label,
     pub progress_notice: nwg::Notice,
-    pub progress_tx: mpsc::Sender<f64>,
-    pub progress_rx: mpsc::Receiver<f64>,
+    pub progress_tx: mpsc::Sender<ProgressUpdate>,
+    pub progress_rx: mpsc::Receiver<ProgressUpdate>,
     pub cancel_update: Arc<Mutex<bool>>,
     pub thread_handle: Rc<RefCell<Option<JoinHandle<()>>>>,
     pub thread_finished: nwg::Notice,
@@ -40,7 +108,7 @@ pub struct Updater {
 
 impl Updater {
     pub fn new(updates: Arc<Vec<ComponentUpdates>>) -> Self {
-        let (progress_tx, progress_rx) = mpsc::channel::<f64>();
+        let (progress_tx, progress_rx) = mpsc::channel::<ProgressUpdate>();
         let (scrollbuffer_tx, scrollbuffer_rx) = mpsc::channel::<String>();
         Self {
             updates,
@@ -52,6 +120,8 @@ impl Updater {
             scrollbuffer_rx,
             scrollbuffer_notice: Default::default(),
             progress_bar: Default::default(),
+            progress_marquee: Cell::new(false),
+            progress_label: Default::default(),
             progress_notice: Default::default(),
             progress_tx,
             progress_rx,
@@ -84,8 +154,98 @@ impl Updater {
     }
 
     pub fn on_progress_notice(&self) {
-        for progress in self.progress_rx.try_iter() {
-            self.progress_bar.set_pos(progress as u32);
+        for update in self.progress_rx.try_iter() {
+            match update {
+                ProgressUpdate::Download(event, overall) => {
+                    // The bar shows the whole update if possible, the current file otherwise
+                    match overall.or(event.percentage()) {
+                        Some(percentage) => {
+                            self.set_progress_marquee(false);
+                            self.progress_bar.set_pos(percentage as u32);
+                        }
+                        // Without a known size there's nothing to fill the bar with
+                        None => self.set_progress_marquee(true),
+                    }
+
+                    let mut text = Self::format_progress(&event);
+                    if let Some(overall) = overall {
+                        text = format!("{overall:.0}% of the update, {text}");
+                    }
+                    self.progress_label.set_text(text.as_str());
+                }
+                ProgressUpdate::Finish => {
+                    self.set_progress_marquee(false);
+                    self.progress_bar.set_pos(100);
+                    self.progress_label.set_text("");
+                }
+            }
+        }
+    }
+
+    fn set_progress_marquee(&self, enable: bool) {
+        if self.progress_marquee.replace(enable) == enable {
+            return;
+        }
+
+        match enable {
+            true => self.progress_bar.add_flags(nwg::ProgressBarFlags::MARQUEE),
+            false => self
+                .progress_bar
+                .remove_flags(nwg::ProgressBarFlags::MARQUEE),
+        }
+        self.progress_bar.set_marquee(enable, 30);
+    }
+
+    fn format_progress(event: &http::ProgressEvent) -> String {
+        let mut text = match event.total {
+            Some(total) => format!(
+                "{} of {}",
+                Self::format_size(event.downloaded),
+                Self::format_size(total)
+            ),
+            None => Self::format_size(event.downloaded),
+        };
+
+        if event.speed > 0.0 {
+            text.push_str(
+                format!(
+                    ", {}/s (average {}/s)",
+                    Self::format_size(event.speed as u64),
+                    Self::format_size(event.average_speed as u64)
+                )
+                .as_str(),
+            );
+            if let Some(eta) = event.eta {
+                text.push_str(format!(", {} left", Self::format_duration(eta)).as_str());
+            }
+        } else if event.downloaded > 0 {
+            text.push_str(", waiting for data...");
+        }
+        text
+    }
+
+    pub fn format_size(bytes: u64) -> String {
+        const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+
+        let mut size = bytes as f64;
+        let mut unit = 0;
+        while size >= 1024.0 && unit < UNITS.len() - 1 {
+            size /= 1024.0;
+            unit += 1;
+        }
+
+        match unit {
+            0 => format!("{bytes} B"),
+            _ => format!("{size:.1} {}", UNITS[unit]),
+        }
+    }
+
+    fn format_duration(duration: Duration) -> String {
+        let seconds = duration.as_secs();
+        match seconds {
+            0..60 => format!("{seconds}s"),
+            60..3600 => format!("{}m {:02}s", seconds / 60, seconds % 60),
+            _ => format!("{}h {:02}m", seconds / 3600, seconds / 60 % 60),
         }
     }
 
@@ -223,50 +383,63 @@ impl Updater {
         let thread_noticer = self.thread_finished.sender();
 
         let thread_handle = thread::spawn(move || {
+            let overall_progress = Rc::new(RefCell::new(OverallProgress::new(updates.as_ref())));
             let progress_callback = Self::create_progress_callback(
                 progress_sender.clone(),
                 progress_noticer,
                 cancel_update,
+                Rc::clone(&overall_progress),
             );
 
             let status_report = |status: String| {
                 Self::send_to_ui(&scrollbuffer_sender, &scrollbuffer_noticer, status);
             };
 
+            for (_, component) in updates.as_ref() {
+                if let Some(notes) = component.release_notes() {
+                    // Release notes must not switch colors
+                    let notes = notes.replace("$[", "$ [");
+                    status_report(format!("{BLUE}{} release notes:", component.name()));
+                    status_report(format!("{RESET}{notes}\n"));
+                }
+            }
+
             let mut all_ok = true;
             for (updates, component) in updates.as_ref() {
                 status_report(format!("{BLUE}Updating {}...", component.name()));
 
-                Self::send_to_ui(&progress_sender, &progress_noticer, 0.0);
-
-                match component.update(updates, &status_report, &progress_callback) {
-                    Err(err) => match err.downcast_ref::<curl::Error>() {
-                        // User abort
-                        Some(curl_err) if curl_err.is_aborted_by_callback() => {
-                            status_report(format!("{ORANGE}Update aborted"));
-                            return;
-                        }
-                        // Update error
-                        _ => {
-                            all_ok = false;
-                            status_report(format!(
-                                "{RED}Failed to update {}: {}",
-                                component.name(),
-                                err
-                            ));
-                        }
-                    },
-                    // Update successful
-                    Ok(_) => {
+                overall_progress.borrow_mut().start_component(updates);
+                let result = super::update_component(
+                    component.as_ref(),
+                    updates,
+                    &status_report,
+                    &progress_callback,
+                );
+                overall_progress.borrow_mut().finish_component();
+
+                match result {
+                    UpdateResult::Aborted => {
+                        status_report(format!("{ORANGE}Update aborted"));
+                        return;
+                    }
+                    UpdateResult::Failed(err) => {
+                        all_ok = false;
+                        status_report(format!(
+                            "{RED}Failed to update {}: {}",
+                            component.name(),
+                            err
+                        ));
+                    }
+                    UpdateResult::Updated => {
                         status_report(format!(
                             "{GREEN}Successfully updated{RESET} {}\n",
                             component.name()
                         ));
-                        Self::send_to_ui(&progress_sender, &progress_noticer, 100.0);
                     }
                 };
             }
 
+            Self::send_to_ui(&progress_sender, &progress_noticer, ProgressUpdate::Finish);
             match all_ok {
                 true => status_report(format!("{GREEN}Update successful")),
                 false => status_report(format!("{RED}There were some errors during updating")),
@@ -284,12 +457,19 @@ impl Updater {
     }
 
     fn create_progress_callback(
-        sender: mpsc::Sender<f64>,
+        sender: mpsc::Sender<ProgressUpdate>,
         noticer: nwg::NoticeSender,
         cancel_update: Arc<Mutex<bool>>,
+        overall_progress: Rc<RefCell<OverallProgress>>,
     ) -> http::ProgressCallback {
-        http::ProgressCallback::new(move |p: f64| {
-            if sender.send(p).is_ok() {
+        let file_overall_progress = Rc::clone(&overall_progress);
+
+        http::ProgressCallback::new(move |event: &http::ProgressEvent| {
+            let overall = overall_progress.borrow_mut().update(event);
+            if sender
+                .send(ProgressUpdate::Download(event.clone(), overall))
+                .is_ok()
+            {
                 noticer.notice();
             }
             match cancel_update.lock() {
@@ -297,6 +477,7 @@ impl Updater {
                 _ => true, // continue
             }
         })
+        .on_start(move || file_overall_progress.borrow_mut().start_file())
     }
 
     fn send_to_ui<T>(sender: &mpsc::Sender<T>, noticer: &nwg::NoticeSender, data: T) {